@@ -1,6 +1,8 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use axum::{Router, http::Request};
+use axum::extract::State;
 use axum::middleware::{self, Next};
 use axum::body::Body;
 use axum::response::Response;
@@ -9,31 +11,62 @@ use tracing::{error, info};
 use axum::http::StatusCode;
 use crate::bootstrap::no_auth_path;
 use crate::utils::jwt_util::with_user_id_scope;
+use crate::service::token_service;
 
 use crate::utils::jwt_util::JwtService;
+use crate::service::address_service::AddressAllocator;
+use crate::service::attribution_service::AttributionStrategy;
+use crate::service::payment_service::PaymentConfig;
+use crate::service::view_service::ViewCounter;
+use crate::service::webhook_service::WebhookConfig;
+use crate::utils::mailer::Mailer;
+use crate::utils::markdown::RenderCache;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: SqlitePool,
+    pub mailer: Arc<dyn Mailer>,
+    pub addresses: Arc<AddressAllocator>,
+    pub attribution: Arc<dyn AttributionStrategy>,
+    pub views: Arc<ViewCounter>,
+    pub renders: Arc<RenderCache>,
+    pub settings: Arc<AppSettings>,
+}
+
+// 运行期业务配置（由 bootstrap 从环境变量加载）
+pub struct AppSettings {
+    // 前端站点地址，用于拼接邮件中的链接
+    pub public_url: String,
+    // 付费操作（如下单）是否要求邮箱已验证
+    pub require_verified_email: bool,
+    // 未支付订单的有效期，超时后由后台任务置为 expired
+    pub order_expiry: chrono::Duration,
+    // 确认数要求（对账与 webhook 共用）
+    pub payment: PaymentConfig,
+    pub webhook: WebhookConfig,
+    // 部署在反向代理之后时，以 X-Forwarded-For 的第一个地址作为客户端 IP
+    pub trust_forwarded_for: bool,
 }
 
 pub async fn init() -> anyhow::Result<(AppState, SocketAddr)> {
     crate::bootstrap::initialize().await
 }
 
-// 构建应用路由（类型为 Router<AppState>，尚未挂载具体 State 实例）
-pub fn new() -> Router<AppState> {
+// 构建应用路由并挂载全局 State（鉴权中间件需要访问数据库）
+pub fn new(state: AppState) -> Router {
     let router = crate::router::build_router();
     let router = crate::router::configure_router(router);
-    router.layer(middleware::from_fn(authorize))
+    router
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
 }
 
-// 启动 HTTP 服务（内部挂载全局 State）
+// 启动 HTTP 服务
 pub async fn serve(addr: SocketAddr, state: AppState) -> anyhow::Result<()> {
-    let app = new().with_state(state);
+    let app = new(state);
     info!("starting server on http://{}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await.map_err(|e| {
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.map_err(|e| {
         error!(error = %e, "server error");
         e.into()
     })
@@ -44,7 +77,11 @@ pub async fn run() -> anyhow::Result<()> {
     serve(addr, state).await
 }
 
-async fn authorize(mut req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+async fn authorize(
+    State(state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
     if req.method() == axum::http::Method::OPTIONS {
         return Ok(next.run(req).await);
     }
@@ -53,7 +90,15 @@ async fn authorize(mut req: Request<Body>, next: Next) -> Result<Response, Statu
     let mut user_id: Option<String> = None;
     if let Some(tok) = token_opt {
         if let Ok(claims) = JwtService::verify_token(&tok) {
-            user_id = Some(claims.sub);
+            let revoked = token_service::is_token_revoked(&state.db, &claims)
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "failed to check token denylist");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            if !revoked {
+                user_id = Some(claims.sub);
+            }
         }
     }
     if let Some(uid) = &user_id {
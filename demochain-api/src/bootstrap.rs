@@ -1,23 +1,386 @@
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use crate::app::AppState;
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::app::{AppSettings, AppState};
+use crate::service::address_service::AddressAllocator;
+use crate::service::attribution_service::{AddressAttribution, AttributionStrategy, UniqueAmountAttribution};
+use crate::service::payment_service::{self, PaymentConfig};
+use crate::service::view_service::{self, ViewCounter};
+use crate::service::webhook_service::WebhookConfig;
+use crate::utils::{hd_wallet, money};
+use crate::utils::chain_indexer::{ChainIndexer, MockIndexer};
+use crate::utils::mailer::{LogMailer, Mailer, SmtpMailer};
+use crate::utils::markdown::RenderCache;
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
-use tracing::info;
+use tracing::{info, warn};
 use dotenvy::dotenv;
+use anyhow::{bail, Context};
+use jsonwebtoken::Algorithm;
+use crate::utils::jwt_util::{
+    JwtKey, JwtKeySet, JwtService, DEFAULT_REFRESH_TOKEN_EXPIRY_SECONDS, DEFAULT_TOKEN_EXPIRY_SECONDS,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 struct AppConfig {
     database_url: String,
     port: u16,
+    public_url: String,
+    require_verified_email: bool,
+    // 未支付订单有效期（ORDER_EXPIRY_MINUTES，默认 60）
+    order_expiry_minutes: i64,
+    // 反向代理之后信任 X-Forwarded-For（TRUST_X_FORWARDED_FOR，默认关闭）
+    trust_forwarded_for: bool,
+    // 文章阅读去重窗口（VIEW_DEDUP_MINUTES，默认 30）与写库间隔（VIEW_FLUSH_SECONDS，默认 10）
+    view_dedup_minutes: u64,
+    view_flush_seconds: u64,
+    // 阅读去重记录上限（VIEW_DEDUP_CAPACITY，默认 100000）
+    view_dedup_capacity: usize,
+    // 文章渲染缓存条数（ARTICLE_RENDER_CACHE_SIZE，默认 256）
+    render_cache_size: usize,
+    jwt: JwtConfig,
+    mail: MailConfig,
+    payment: PaymentWatcherConfig,
+    address: AddressConfig,
 }
 
-impl AppConfig {
+// JWT 签名配置：
+// - JWT_ALGORITHM: HS256（默认）| RS256 | EdDSA
+// - JWT_KID: 当前签名密钥的 kid（默认 "default"）
+// - HS256: JWT_SECRET 或 JWT_SECRET_FILE
+// - RS256/EdDSA: JWT_PRIVATE_KEY_FILE + JWT_PUBLIC_KEY_FILE（PEM）
+// - JWT_RETIRED_KEYS: 轮换下来仍需验签的旧密钥，格式 kid=alg:path,kid=alg:path
+//   （alg 省略时沿用 JWT_ALGORITHM；HS256 为密钥文件，RS256/EdDSA 为公钥 PEM）
+// - 未配置签名密钥时启动失败；仅本地开发可设置 JWT_ALLOW_EPHEMERAL=1 使用进程内随机密钥
+// - JWT_EXPIRY_SECONDS / JWT_REFRESH_EXPIRY_SECONDS: 令牌有效期
+#[derive(Clone)]
+struct JwtConfig {
+    algorithm: Algorithm,
+    kid: String,
+    secret: Option<String>,
+    private_key_file: Option<String>,
+    public_key_file: Option<String>,
+    allow_ephemeral: bool,
+    retired_keys: Vec<RetiredKey>,
+    token_ttl: i64,
+    refresh_token_ttl: i64,
+}
+
+#[derive(Clone)]
+struct RetiredKey {
+    kid: String,
+    algorithm: Algorithm,
+    path: String,
+}
+
+// 邮件配置：MAIL_TRANSPORT=smtp | log（默认 log）
+// - smtp: SMTP_HOST, SMTP_PORT（默认 465）, SMTP_USERNAME, SMTP_PASSWORD, MAIL_FROM
+// - log: MAIL_LOG_DIR（可选，设置后每封邮件写成一个文件）
+#[derive(Clone)]
+enum MailConfig {
+    Smtp {
+        host: String,
+        port: u16,
+        username: String,
+        password: String,
+        from: String,
+    },
+    Log {
+        dir: Option<String>,
+    },
+}
+
+// 支付对账配置：PAYMENT_INDEXER=none（默认，不启动对账）| mock
+// - mock: PAYMENT_MOCK_FILE（JSON 转账列表，见 MockIndexer）
+// - PAYMENT_POLL_SECONDS: 轮询间隔（默认 30）
+// - PAYMENT_CONFIRMATIONS: 统一确认数（默认按网络：trc20=19, erc20=12, bep20=15）
+// - PAYMENT_WEBHOOK_SECRETS: webhook 推送方密钥，格式 provider=secret,...（未配置则拒绝所有推送）
+// - PAYMENT_WEBHOOK_TOLERANCE_SECONDS: webhook 时间戳允许偏差（默认 300）
+#[derive(Clone)]
+struct PaymentWatcherConfig {
+    indexer: Option<String>,
+    mock_file: Option<String>,
+    watcher: PaymentConfig,
+    webhook: WebhookConfig,
+}
+
+// 收款地址配置：
+// - HD_XPUB_EVM / HD_XPUB_TRON: 外部链扩展公钥（如 m/44'/60'/0'/0、m/44'/195'/0'/0），
+//   配置后每个订单派生独立收款地址；服务端只需要公钥
+// - RECEIVE_ADDRESS_EVM / RECEIVE_ADDRESS_TRON: 未配置 xpub 时使用的固定收款地址
+// - PAYMENT_ATTRIBUTION: address（默认，按地址归属）| unique_amount（共享地址的订单附加唯一金额尾差）
+// - PAYMENT_AMOUNT_STEP / PAYMENT_AMOUNT_SLOTS: 尾差步长与可用数量（默认 0.0001 / 100）
+#[derive(Clone)]
+struct AddressConfig {
+    evm_xpub: Option<String>,
+    tron_xpub: Option<String>,
+    evm_fallback: Option<String>,
+    tron_fallback: Option<String>,
+    attribution: String,
+    amount_step: String,
+    amount_slots: u32,
+}
+
+impl AddressConfig {
     fn load() -> Self {
-        dotenv().ok();
+        Self {
+            evm_xpub: std::env::var("HD_XPUB_EVM").ok(),
+            tron_xpub: std::env::var("HD_XPUB_TRON").ok(),
+            evm_fallback: Some(
+                std::env::var("RECEIVE_ADDRESS_EVM")
+                    .unwrap_or_else(|_| "0x909b17701d00c156b630C92497fdc1f1ae39fED4".to_string()),
+            ),
+            tron_fallback: std::env::var("RECEIVE_ADDRESS_TRON").ok(),
+            attribution: std::env::var("PAYMENT_ATTRIBUTION").unwrap_or_else(|_| "address".to_string()).to_lowercase(),
+            amount_step: std::env::var("PAYMENT_AMOUNT_STEP").unwrap_or_else(|_| "0.0001".to_string()),
+            amount_slots: std::env::var("PAYMENT_AMOUNT_SLOTS").ok().and_then(|s| s.parse().ok()).unwrap_or(100),
+        }
+    }
+
+    fn build_attribution(&self) -> anyhow::Result<Arc<dyn AttributionStrategy>> {
+        match self.attribution.as_str() {
+            "address" | "" => Ok(Arc::new(AddressAttribution)),
+            "unique_amount" => {
+                let step = money::parse(&self.amount_step).context("PAYMENT_AMOUNT_STEP")?;
+                Ok(Arc::new(UniqueAmountAttribution::new(step, self.amount_slots)?))
+            }
+            other => bail!("unsupported PAYMENT_ATTRIBUTION: {}", other),
+        }
+    }
+
+    fn build_allocator(&self) -> anyhow::Result<AddressAllocator> {
+        let evm_xpub = self.evm_xpub.as_deref().map(hd_wallet::parse_xpub).transpose().context("HD_XPUB_EVM")?;
+        let tron_xpub = self.tron_xpub.as_deref().map(hd_wallet::parse_xpub).transpose().context("HD_XPUB_TRON")?;
+        if evm_xpub.is_none() {
+            warn!("HD_XPUB_EVM not set, ERC20/BEP20 orders share a fixed receive address");
+        }
+        if tron_xpub.is_none() {
+            warn!("HD_XPUB_TRON not set, TRC20 orders share a fixed receive address");
+        }
+        Ok(AddressAllocator::new(evm_xpub, tron_xpub, self.evm_fallback.clone(), self.tron_fallback.clone()))
+    }
+}
+
+impl AppConfig {
+    fn load() -> anyhow::Result<Self> {
         let database_url = std::env::var("DATABASE_URL")
             .unwrap_or_else(|_| "sqlite://./data/demochain.db".to_string());
         let port = std::env::var("PORT").ok().and_then(|s| s.parse().ok()).unwrap_or(8085);
-        Self { database_url, port }
+        let public_url = std::env::var("PUBLIC_URL")
+            .unwrap_or_else(|_| "https://demochain.org".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let require_verified_email = env_flag("REQUIRE_VERIFIED_EMAIL");
+        let order_expiry_minutes = std::env::var("ORDER_EXPIRY_MINUTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|m: &i64| *m > 0)
+            .unwrap_or(60);
+        let trust_forwarded_for = env_flag("TRUST_X_FORWARDED_FOR");
+        let view_dedup_minutes = std::env::var("VIEW_DEDUP_MINUTES").ok().and_then(|s| s.parse().ok()).unwrap_or(30);
+        let view_flush_seconds = std::env::var("VIEW_FLUSH_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s: &u64| *s > 0)
+            .unwrap_or(10);
+        let view_dedup_capacity = std::env::var("VIEW_DEDUP_CAPACITY").ok().and_then(|s| s.parse().ok()).unwrap_or(100_000);
+        let render_cache_size = std::env::var("ARTICLE_RENDER_CACHE_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(256);
+        let jwt = JwtConfig::load()?;
+        let mail = MailConfig::load()?;
+        let payment = PaymentWatcherConfig::load()?;
+        let address = AddressConfig::load();
+        Ok(Self {
+            database_url,
+            port,
+            public_url,
+            require_verified_email,
+            order_expiry_minutes,
+            trust_forwarded_for,
+            view_dedup_minutes,
+            view_flush_seconds,
+            view_dedup_capacity,
+            render_cache_size,
+            jwt,
+            mail,
+            payment,
+            address,
+        })
+    }
+}
+
+impl PaymentWatcherConfig {
+    fn load() -> anyhow::Result<Self> {
+        let indexer = match std::env::var("PAYMENT_INDEXER").unwrap_or_else(|_| "none".to_string()).to_lowercase().as_str() {
+            "none" | "" => None,
+            "mock" => Some("mock".to_string()),
+            other => bail!("unsupported PAYMENT_INDEXER: {}", other),
+        };
+        let poll_seconds = std::env::var("PAYMENT_POLL_SECONDS").ok().and_then(|s| s.parse().ok()).unwrap_or(30);
+        let secrets = std::env::var("PAYMENT_WEBHOOK_SECRETS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|entry| {
+                entry
+                    .split_once('=')
+                    .map(|(provider, secret)| (provider.trim().to_string(), secret.trim().as_bytes().to_vec()))
+                    .context("invalid PAYMENT_WEBHOOK_SECRETS entry, expected provider=secret")
+            })
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+        let tolerance_seconds = std::env::var("PAYMENT_WEBHOOK_TOLERANCE_SECONDS").ok().and_then(|s| s.parse().ok()).unwrap_or(300);
+        Ok(Self {
+            indexer,
+            mock_file: std::env::var("PAYMENT_MOCK_FILE").ok(),
+            watcher: PaymentConfig {
+                poll_interval: std::time::Duration::from_secs(poll_seconds),
+                confirmations: std::env::var("PAYMENT_CONFIRMATIONS").ok().and_then(|s| s.parse().ok()),
+            },
+            webhook: WebhookConfig {
+                secrets,
+                tolerance: std::time::Duration::from_secs(tolerance_seconds),
+            },
+        })
+    }
+
+    fn build_indexer(&self) -> Option<Arc<dyn ChainIndexer>> {
+        match self.indexer.as_deref() {
+            Some("mock") => Some(Arc::new(MockIndexer::new(self.mock_file.as_ref().map(PathBuf::from)))),
+            _ => None,
+        }
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    matches!(
+        std::env::var(key).unwrap_or_default().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+impl MailConfig {
+    fn load() -> anyhow::Result<Self> {
+        match std::env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "log".to_string()).to_lowercase().as_str() {
+            "smtp" => {
+                let var = |key: &str| std::env::var(key).with_context(|| format!("{} is required for smtp mail", key));
+                Ok(MailConfig::Smtp {
+                    host: var("SMTP_HOST")?,
+                    port: std::env::var("SMTP_PORT").ok().and_then(|s| s.parse().ok()).unwrap_or(465),
+                    username: var("SMTP_USERNAME")?,
+                    password: var("SMTP_PASSWORD")?,
+                    from: var("MAIL_FROM")?,
+                })
+            }
+            "log" => Ok(MailConfig::Log { dir: std::env::var("MAIL_LOG_DIR").ok() }),
+            other => bail!("unsupported MAIL_TRANSPORT: {}", other),
+        }
+    }
+
+    fn build_mailer(&self) -> anyhow::Result<Arc<dyn Mailer>> {
+        Ok(match self {
+            MailConfig::Smtp { host, port, username, password, from } => {
+                Arc::new(SmtpMailer::new(host, *port, username.clone(), password.clone(), from)?)
+            }
+            MailConfig::Log { dir } => Arc::new(LogMailer::new(dir.as_ref().map(PathBuf::from))),
+        })
+    }
+}
+
+impl JwtConfig {
+    fn load() -> anyhow::Result<Self> {
+        let algorithm = parse_jwt_algorithm(&std::env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()))
+            .context("unsupported JWT_ALGORITHM")?;
+        let kid = std::env::var("JWT_KID").unwrap_or_else(|_| "default".to_string());
+        let secret = match (std::env::var("JWT_SECRET"), std::env::var("JWT_SECRET_FILE")) {
+            (Ok(secret), _) => Some(secret),
+            (Err(_), Ok(path)) => Some(
+                std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read JWT_SECRET_FILE {}", path))?
+                    .trim()
+                    .to_string(),
+            ),
+            _ => None,
+        };
+        let retired_keys = std::env::var("JWT_RETIRED_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|entry| {
+                let (kid, value) = entry
+                    .split_once('=')
+                    .with_context(|| format!("invalid JWT_RETIRED_KEYS entry: {}", entry))?;
+                // alg: 前缀可选，未识别时整体视为路径
+                let (key_algorithm, path) = value
+                    .split_once(':')
+                    .and_then(|(alg, path)| parse_jwt_algorithm(alg).map(|alg| (alg, path)))
+                    .unwrap_or((algorithm, value));
+                Ok(RetiredKey { kid: kid.trim().to_string(), algorithm: key_algorithm, path: path.trim().to_string() })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let token_ttl = std::env::var("JWT_EXPIRY_SECONDS").ok().and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_TOKEN_EXPIRY_SECONDS);
+        let refresh_token_ttl = std::env::var("JWT_REFRESH_EXPIRY_SECONDS").ok().and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_TOKEN_EXPIRY_SECONDS);
+        Ok(Self {
+            algorithm,
+            kid,
+            secret,
+            private_key_file: std::env::var("JWT_PRIVATE_KEY_FILE").ok(),
+            public_key_file: std::env::var("JWT_PUBLIC_KEY_FILE").ok(),
+            allow_ephemeral: env_flag("JWT_ALLOW_EPHEMERAL"),
+            retired_keys,
+            token_ttl,
+            refresh_token_ttl,
+        })
+    }
+
+    fn build_key_set(&self) -> anyhow::Result<JwtKeySet> {
+        let read = |path: &str| std::fs::read(path).with_context(|| format!("failed to read key file {}", path));
+
+        let active = match self.algorithm {
+            Algorithm::HS256 => match &self.secret {
+                Some(secret) => JwtKey::from_secret(self.kid.clone(), secret.as_bytes()),
+                None => {
+                    if !self.allow_ephemeral {
+                        bail!("JWT_SECRET or JWT_SECRET_FILE is required (set JWT_ALLOW_EPHEMERAL=1 for local development only)");
+                    }
+                    if !self.retired_keys.is_empty() {
+                        bail!("JWT_RETIRED_KEYS is set but no JWT_SECRET is configured");
+                    }
+                    warn!("JWT_SECRET not set, using an ephemeral secret; tokens will not survive a restart");
+                    let mut key_set = JwtKeySet::ephemeral();
+                    key_set.token_ttl = self.token_ttl;
+                    key_set.refresh_token_ttl = self.refresh_token_ttl;
+                    return Ok(key_set);
+                }
+            },
+            algorithm => {
+                let private_file = self.private_key_file.as_deref().context("JWT_PRIVATE_KEY_FILE is required")?;
+                let public_file = self.public_key_file.as_deref().context("JWT_PUBLIC_KEY_FILE is required")?;
+                JwtKey::from_pem(self.kid.clone(), algorithm, Some(&read(private_file)?), &read(public_file)?)?
+            }
+        };
+
+        let mut retired = Vec::with_capacity(self.retired_keys.len());
+        for RetiredKey { kid, algorithm, path } in &self.retired_keys {
+            let material = read(path)?;
+            let key = match algorithm {
+                Algorithm::HS256 => JwtKey::from_secret(kid.clone(), String::from_utf8_lossy(&material).trim().as_bytes()),
+                algorithm => JwtKey::from_pem(kid.clone(), *algorithm, None, &material)?,
+            };
+            retired.push(key);
+        }
+
+        JwtKeySet::new(active, retired, self.token_ttl, self.refresh_token_ttl)
+    }
+}
+
+fn parse_jwt_algorithm(name: &str) -> Option<Algorithm> {
+    match name.trim().to_uppercase().as_str() {
+        "HS256" => Some(Algorithm::HS256),
+        "RS256" => Some(Algorithm::RS256),
+        "EDDSA" => Some(Algorithm::EdDSA),
+        _ => None,
     }
 }
 
@@ -47,15 +410,51 @@ async fn connect_pool(database_url: &str) -> anyhow::Result<SqlitePool> {
 
 pub async fn initialize() -> anyhow::Result<(AppState, SocketAddr)> {
     // 1) 配置与日志
-    let cfg = AppConfig::load();
+    dotenv().ok();
     init_tracing();
+    let cfg = AppConfig::load()?;
+    JwtService::init(cfg.jwt.build_key_set()?)?;
+    info!(algorithm = ?cfg.jwt.algorithm, kid = %cfg.jwt.kid, retired = cfg.jwt.retired_keys.len(), "jwt keys loaded");
 
     // 2) 数据目录与连接
     ensure_sqlite_dir(&cfg.database_url);
     let pool = connect_pool(&cfg.database_url).await?;
+    match crate::service::article_service::backfill_slugs(&pool).await {
+        Ok(0) => {}
+        Ok(n) => info!(articles = n, "generated missing article slugs"),
+        Err(e) => warn!(error = %e, "failed to generate article slugs"),
+    }
+
+    // 3) 后台任务
+    crate::service::token_service::spawn_cleanup_task(pool.clone());
+    crate::service::order_service::spawn_expiry_sweeper(pool.clone());
+    let views = Arc::new(ViewCounter::new(
+        std::time::Duration::from_secs(cfg.view_dedup_minutes * 60),
+        cfg.view_dedup_capacity,
+    ));
+    view_service::spawn_flusher(pool.clone(), views.clone(), std::time::Duration::from_secs(cfg.view_flush_seconds));
+    match cfg.payment.build_indexer() {
+        Some(indexer) => payment_service::spawn_watcher(pool.clone(), indexer, cfg.payment.watcher.clone()),
+        None => warn!("PAYMENT_INDEXER not configured, payment watcher disabled"),
+    }
 
-    // 3) 装配返回
-    let state = AppState { db: pool };
+    // 4) 装配返回
+    let state = AppState {
+        db: pool,
+        mailer: cfg.mail.build_mailer()?,
+        addresses: Arc::new(cfg.address.build_allocator()?),
+        attribution: cfg.address.build_attribution()?,
+        views,
+        renders: Arc::new(RenderCache::new(cfg.render_cache_size)),
+        settings: Arc::new(AppSettings {
+            public_url: cfg.public_url.clone(),
+            require_verified_email: cfg.require_verified_email,
+            order_expiry: chrono::Duration::minutes(cfg.order_expiry_minutes),
+            payment: cfg.payment.watcher.clone(),
+            webhook: cfg.payment.webhook.clone(),
+            trust_forwarded_for: cfg.trust_forwarded_for,
+        }),
+    };
     let addr = SocketAddr::from(([0, 0, 0, 0], cfg.port));
     info!("initialized with addr=http://{} database_url={}", addr, cfg.database_url);
     Ok((state, addr))
@@ -66,7 +465,8 @@ pub fn no_auth_path(path: &str) -> bool {
         path,
         "/" | "/health" | "/api/health" |
         "/api/auth/Login" | "/api/auth/register" | "/api/auth/refresh" |
-        "/api/blogs/page" | "/api/term/page" |
+        "/api/auth/reset-password/request" | "/api/auth/reset-password/confirm" | "/api/auth/verify-email" |
+        "/api/blogs/page" | "/api/term/page" | "/api/plan/list" |
         "/docs" | "/swagger" | "/openapi.json"
-    ) || path.starts_with("/assets/") || path.starts_with("/public/") || path.starts_with("/api/article/") || path.starts_with("/api/term/")
+    ) || path.starts_with("/assets/") || path.starts_with("/public/") || path.starts_with("/api/article/") || path.starts_with("/api/term/") || path.starts_with("/api/payment/webhook/")
 }
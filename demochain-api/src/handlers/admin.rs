@@ -0,0 +1,122 @@
+use crate::app::AppState;
+use crate::models::order::{AdminActionDTO, AdminOrderQuery, OrderVO, UnmatchedTransferQuery, UnmatchedTransferVO};
+use crate::models::{PageVO, R};
+use crate::service::{order_service, payment_service, user_service};
+use crate::utils::role_util::{Admin, RequireRole};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+
+// 操作人记录为 admin:<用户ID>，便于在订单状态记录中追溯
+fn actor(admin: &RequireRole<Admin>) -> String {
+    format!("admin:{}", admin.claims.sub)
+}
+
+// 人工确认与退款必须填写原因
+fn required_reason(payload: Option<Json<AdminActionDTO>>) -> Option<String> {
+    payload
+        .and_then(|Json(p)| p.reason)
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+}
+
+fn missing_reason() -> Json<R<()>> {
+    Json(R { success: false, data: None, message: Some("请填写操作原因".to_string()), code: Some(400) })
+}
+
+// 跨用户查询订单
+pub async fn order_page(
+    State(state): State<AppState>,
+    _admin: RequireRole<Admin>,
+    Query(query): Query<AdminOrderQuery>,
+) -> Json<R<PageVO<OrderVO>>> {
+    match order_service::search(&state.db, query).await {
+        Ok(paged) => Json(R { success: true, data: Some(paged), message: None, code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    }
+}
+
+// 人工确认订单（如链上到账但未自动匹配）
+pub async fn confirm_order(
+    State(state): State<AppState>,
+    admin: RequireRole<Admin>,
+    Path(id): Path<i64>,
+    payload: Option<Json<AdminActionDTO>>,
+) -> Json<R<()>> {
+    let Some(reason) = required_reason(payload) else {
+        return missing_reason();
+    };
+    match order_service::confirm(&state.db, id, &actor(&admin), Some(&reason), None, None).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("订单已确认".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+// 退款并撤销权益
+pub async fn refund_order(
+    State(state): State<AppState>,
+    admin: RequireRole<Admin>,
+    Path(id): Path<i64>,
+    payload: Option<Json<AdminActionDTO>>,
+) -> Json<R<()>> {
+    let Some(reason) = required_reason(payload) else {
+        return missing_reason();
+    };
+    match order_service::refund(&state.db, id, &actor(&admin), &reason).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("订单已退款".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+// 未能匹配订单的到账（如订单过期后才到账），需人工对账
+pub async fn unmatched_transfers(
+    State(state): State<AppState>,
+    _admin: RequireRole<Admin>,
+    Query(query): Query<UnmatchedTransferQuery>,
+) -> Json<R<PageVO<UnmatchedTransferVO>>> {
+    match payment_service::unmatched_page(&state.db, query).await {
+        Ok(paged) => Json(R { success: true, data: Some(paged), message: None, code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    }
+}
+
+// 标记未匹配到账已处理，必须填写处理说明
+pub async fn resolve_unmatched_transfer(
+    State(state): State<AppState>,
+    admin: RequireRole<Admin>,
+    Path(id): Path<i64>,
+    payload: Option<Json<AdminActionDTO>>,
+) -> Json<R<()>> {
+    let Some(reason) = required_reason(payload) else {
+        return missing_reason();
+    };
+    match payment_service::resolve_unmatched(&state.db, id, &actor(&admin), &reason).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("已标记为已处理".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+// 禁用账号
+pub async fn disable_user(
+    State(state): State<AppState>,
+    admin: RequireRole<Admin>,
+    Path(id): Path<String>,
+    payload: Option<Json<AdminActionDTO>>,
+) -> Json<R<()>> {
+    let reason = required_reason(payload);
+    match user_service::disable(&state.db, &admin.claims.sub, &id, reason.as_deref()).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("账号已禁用".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+// 解除禁用
+pub async fn enable_user(
+    State(state): State<AppState>,
+    _admin: RequireRole<Admin>,
+    Path(id): Path<String>,
+) -> Json<R<()>> {
+    match user_service::enable(&state.db, &id).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("账号已启用".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
@@ -1,29 +1,186 @@
-use axum::extract::{Query, State, Path};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+use axum::extract::{ConnectInfo, Query, State, Path};
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use crate::app::AppState;
-use crate::models::article::{Article, PageArticleDTO};
+use crate::models::article::{ArticleDTO, ArticleQuery, ArticleRevisionVO, ArticleSummary, PageArticleDTO, SummaryItem, TagVO};
 use crate::models::{PageVO, R};
-use crate::service::article_service;
+use crate::service::article_service::{self, ArticleLookup};
+use crate::utils::{jwt_util, markdown};
+use crate::utils::role_util::{Editor, RequireRole};
 
+// 列表只返回摘要；fields= 可进一步裁剪字段
 pub async fn page(
     State(state): State<AppState>,
     Query(p): Query<PageArticleDTO>,
-) -> Json<R<PageVO<Article>>> {
+) -> Json<R<PageVO<SummaryItem>>> {
     let page = p.page.unwrap_or(1);
     let size = p.size.unwrap_or(10);
-    match article_service::page(&state.db, page, size).await {
-        Ok(paged) => Json(R { success: true, data: Some(paged), message: None, code: Some(200) }),
+    let fields = match article_service::parse_fields(p.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    };
+    let selected = |name: &str| fields.as_ref().is_none_or(|f| f.iter().any(|f| f == name));
+    let (include_tags, include_snippet) = (selected("tags"), selected("snippet"));
+    let paged = match article_service::page(&state.db, page, size, p.q.as_deref(), p.tag.as_deref(), include_tags, include_snippet).await {
+        Ok(paged) => paged,
+        Err(e) => return Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    };
+    // 未指定 fields= 时直接返回完整摘要，无需裁剪
+    let items = match fields {
+        None => Ok(paged.items.into_iter().map(SummaryItem::Full).collect()),
+        Some(fields) => article_service::select_fields(paged.items, &fields)
+            .map(|items| items.into_iter().map(SummaryItem::Selected).collect()),
+    };
+    match items {
+        Ok(items) => Json(R {
+            success: true,
+            data: Some(PageVO { items, total: paged.total, page: paged.page, size: paged.size }),
+            message: None,
+            code: Some(200),
+        }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    }
+}
+
+// 代码高亮样式表，配合 format=html 使用
+pub async fn highlight_css() -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/css; charset=utf-8"), (header::CACHE_CONTROL, "public, max-age=86400")],
+        markdown::highlight_css(),
+    ).into_response()
+}
+
+pub async fn tags(State(state): State<AppState>) -> Json<R<Vec<TagVO>>> {
+    match article_service::tags(&state.db).await {
+        Ok(list) => Json(R { success: true, data: Some(list), message: None, code: Some(200) }),
         Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
     }
 }
 
+// 按 ID 或 slug 查询；旧 slug 以 301 重定向到当前 slug（保留查询参数）；
+// format=html 时附带渲染结果，format=markdown（默认）只返回原文
 pub async fn get_article(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Json<R<Article>> {
-    match article_service::get_by_id(&state.db, &id).await {
-        Ok(Some(article)) => Json(R { success: true, data: Some(article), message: None, code: Some(200) }),
-        Ok(None) => Json(R { success: false, data: None, message: Some("文章不存在".to_string()), code: Some(404) }),
+    Query(query): Query<ArticleQuery>,
+    uri: Uri,
+    headers: HeaderMap,
+    connect: Option<ConnectInfo<SocketAddr>>,
+) -> Response {
+    let render = match query.format.as_deref().map(str::to_ascii_lowercase).as_deref() {
+        None | Some("") | Some("markdown") => false,
+        Some("html") => true,
+        Some(_) => {
+            return Json(R::<()> { success: false, data: None, message: Some("format 只支持 markdown 或 html".to_string()), code: Some(400) }).into_response();
+        }
+    };
+    match article_service::lookup(&state.db, &id).await {
+        Ok(ArticleLookup::Found(mut article)) => {
+            if render {
+                match state.renders.render(article.content.clone()).await {
+                    Ok(rendered) => article.rendered = Some(rendered.as_ref().clone()),
+                    Err(e) => {
+                        return Json(R::<()> { success: false, data: None, message: Some(e.to_string()), code: Some(500) }).into_response();
+                    }
+                }
+            }
+            if let (Ok(article_id), Some(viewer)) = (article.id.parse::<i64>(), viewer_key(&state, &headers, connect)) {
+                // 展示值包含尚未写库的增量
+                let pending = state.views.record(article_id, &viewer);
+                article.views += pending as i32;
+            }
+            Json(R { success: true, data: Some(*article), message: None, code: Some(200) }).into_response()
+        }
+        Ok(ArticleLookup::Moved(slug)) => {
+            let location = match uri.query() {
+                Some(query) => format!("/api/article/{}?{}", slug, query),
+                None => format!("/api/article/{}", slug),
+            };
+            (
+                StatusCode::MOVED_PERMANENTLY,
+                [(header::LOCATION, location)],
+                Json(R::<()> { success: false, data: None, message: Some(slug), code: Some(301) }),
+            ).into_response()
+        }
+        Ok(ArticleLookup::NotFound) => {
+            Json(R::<()> { success: false, data: None, message: Some("文章不存在".to_string()), code: Some(404) }).into_response()
+        }
+        Err(e) => Json(R::<()> { success: false, data: None, message: Some(e.to_string()), code: Some(500) }).into_response(),
+    }
+}
+
+// 阅读去重的访客标识：已登录按用户，否则按客户端 IP（IPv6 按 /64 前缀，同一网段轮换地址视为同一访客）
+fn viewer_key(state: &AppState, headers: &HeaderMap, connect: Option<ConnectInfo<SocketAddr>>) -> Option<String> {
+    if let Some(uid) = jwt_util::get_user_id() {
+        return Some(format!("user:{}", uid));
+    }
+    let forwarded = state.settings.trust_forwarded_for
+        .then(|| headers.get("x-forwarded-for")?.to_str().ok()?.split(',').next()?.trim().parse::<IpAddr>().ok())
+        .flatten();
+    let ip = forwarded.or_else(|| connect.map(|ConnectInfo(addr)| addr.ip()))?;
+    let ip = match ip.to_canonical() {
+        IpAddr::V6(v6) => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & (u128::MAX << 64))),
+        v4 => v4,
+    };
+    Some(format!("ip:{}", ip))
+}
+
+// 以下为编辑接口，需要 editor 或 admin 角色
+pub async fn add(
+    State(state): State<AppState>,
+    editor: RequireRole<Editor>,
+    Json(payload): Json<ArticleDTO>,
+) -> Json<R<ArticleSummary>> {
+    match article_service::create(&state.db, &editor.claims.sub, payload).await {
+        Ok(article) => Json(R { success: true, data: Some(article), message: Some("文章已创建".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    editor: RequireRole<Editor>,
+    Path(id): Path<i64>,
+    Json(payload): Json<ArticleDTO>,
+) -> Json<R<ArticleSummary>> {
+    match article_service::update(&state.db, &editor.claims.sub, id, payload).await {
+        Ok(article) => Json(R { success: true, data: Some(article), message: Some("文章已保存".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    _editor: RequireRole<Editor>,
+    Path(id): Path<i64>,
+) -> Json<R<()>> {
+    match article_service::delete(&state.db, id).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("文章已删除".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+pub async fn revisions(
+    State(state): State<AppState>,
+    _editor: RequireRole<Editor>,
+    Path(id): Path<i64>,
+) -> Json<R<Vec<ArticleRevisionVO>>> {
+    match article_service::revisions(&state.db, id).await {
+        Ok(list) => Json(R { success: true, data: Some(list), message: None, code: Some(200) }),
         Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
     }
-}
\ No newline at end of file
+}
+
+pub async fn restore(
+    State(state): State<AppState>,
+    editor: RequireRole<Editor>,
+    Path((id, revision_id)): Path<(i64, i64)>,
+) -> Json<R<ArticleSummary>> {
+    match article_service::restore(&state.db, &editor.claims.sub, id, revision_id).await {
+        Ok(article) => Json(R { success: true, data: Some(article), message: Some("已恢复到所选版本".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
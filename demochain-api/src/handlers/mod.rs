@@ -3,4 +3,7 @@ pub mod order;
 pub mod article;
 pub mod health;
 pub mod term;
+pub mod plan;
+pub mod payment;
 
+pub mod admin;
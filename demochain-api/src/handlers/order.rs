@@ -1,18 +1,22 @@
 use crate::app::AppState;
-use crate::models::order::{OrderDTO, OrderVO, PageOrderDTO};
+use crate::models::order::{CancelOrderDTO, OrderDTO, OrderDetailQuery, OrderVO, PageOrderDTO};
 use crate::models::{PageVO, R};
 use crate::service::order_service;
-use axum::extract::{Query, State};
+use crate::utils::jwt_util::AuthUser;
+use crate::utils::plan_util::{Paid, RequirePlan};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 
 pub async fn add(
     State(state): State<AppState>,
     Json(payload): Json<OrderDTO>,
-) -> Json<R<String>> {
-    match order_service::add(&state.db, payload).await {
-        Ok(address) => Json(R {
+) -> Json<R<OrderVO>> {
+    match order_service::add(&state.db, &state.addresses, state.attribution.as_ref(), &state.settings, payload).await {
+        Ok(order) => Json(R {
             success: true,
-            data: Some(address),
+            data: Some(order),
             message: Some("订单创建成功".to_string()),
             code: Some(200),
         }),
@@ -34,4 +38,56 @@ pub async fn page(
         Ok(paged) => Json(R { success: true, data: Some(paged), message: None, code: Some(200) }),
         Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
     }
-}
\ No newline at end of file
+}
+
+// 订单详情；?state=<当前状态>&wait=<秒> 时长轮询等待状态变化
+pub async fn detail(
+    State(state): State<AppState>,
+    AuthUser(claims): AuthUser,
+    Path(id): Path<i64>,
+    Query(q): Query<OrderDetailQuery>,
+) -> Json<R<OrderVO>> {
+    let result = match (q.state.as_deref(), q.wait) {
+        (Some(known), Some(wait)) => order_service::wait_for_change(&state.db, &claims.sub, id, known, wait).await,
+        _ => order_service::get(&state.db, &claims.sub, id).await,
+    };
+    match result {
+        Ok(Some(order)) => Json(R { success: true, data: Some(order), message: None, code: Some(200) }),
+        Ok(None) => Json(R { success: false, data: None, message: Some("订单不存在".to_string()), code: Some(404) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    }
+}
+
+// 取消尚未支付的订单
+pub async fn cancel(
+    State(state): State<AppState>,
+    AuthUser(claims): AuthUser,
+    Path(id): Path<i64>,
+    payload: Option<Json<CancelOrderDTO>>,
+) -> Json<R<()>> {
+    let reason = payload.and_then(|Json(p)| p.reason);
+    match order_service::cancel(&state.db, &claims.sub, id, reason.as_deref()).await {
+        Ok(()) => Json(R { success: true, data: None, message: Some("订单已取消".to_string()), code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(400) }),
+    }
+}
+
+// 导出订单为付费功能
+pub async fn export(
+    State(state): State<AppState>,
+    plan: RequirePlan<Paid>,
+) -> Response {
+    match order_service::export_csv(&state.db, &plan.claims.sub).await {
+        Ok(csv) => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"orders.csv\""),
+            ],
+            csv,
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(R::<()> { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+        ).into_response(),
+    }
+}
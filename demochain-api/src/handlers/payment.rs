@@ -0,0 +1,35 @@
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use crate::app::AppState;
+use crate::models::R;
+use crate::service::webhook_service::{self, WebhookOutcome, WebhookSignature};
+
+// 支付 webhook：验签失败 401、格式错误 400、处理失败 500（推送方可重试），其余 200
+pub async fn webhook(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<R<()>>) {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let sig = WebhookSignature {
+        timestamp: header("x-webhook-timestamp"),
+        signature: header("x-webhook-signature"),
+    };
+    let outcome = webhook_service::handle(&state.db, &state.settings.webhook, &state.settings.payment, &provider, sig, &body).await;
+    let (status, code, message) = match outcome {
+        Ok(outcome) => {
+            let status = match outcome {
+                WebhookOutcome::Rejected(_) => StatusCode::UNAUTHORIZED,
+                WebhookOutcome::Invalid(_) => StatusCode::BAD_REQUEST,
+                WebhookOutcome::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::OK,
+            };
+            (status, status.as_u16() as i32, outcome.message())
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, 500, e.to_string()),
+    };
+    (status, Json(R { success: status == StatusCode::OK, data: None, message: Some(message), code: Some(code) }))
+}
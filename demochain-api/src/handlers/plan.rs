@@ -0,0 +1,13 @@
+use axum::extract::State;
+use axum::Json;
+use crate::app::AppState;
+use crate::models::plan::PlanVO;
+use crate::models::R;
+use crate::service::plan_service;
+
+pub async fn list(State(state): State<AppState>) -> Json<R<Vec<PlanVO>>> {
+    match plan_service::list(&state.db).await {
+        Ok(plans) => Json(R { success: true, data: Some(plans), message: None, code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    }
+}
@@ -1,19 +1,23 @@
 use axum::extract::State;
 use axum::Json;
 use crate::app::AppState;
-use crate::models::user::{RegisterDTO, LoginDTO, AuthVO};
+use crate::models::user::{
+    ChangePasswordRequest, ConfirmResetPasswordRequest, RegisterDTO, LoginDTO, LogoutDTO, AuthVO, RefreshTokenDTO,
+    ResetPasswordRequest, TokenVO, UserDetail, VerifyEmailRequest,
+};
 use crate::models::R;
 use crate::service::user_service;
+use crate::utils::jwt_util::AuthUser;
 
 pub async fn register(
     State(state): State<AppState>,
     Json(payload): Json<RegisterDTO>,
 ) -> Json<R<()>> {
-    match user_service::register(&state.db, payload).await {
+    match user_service::register(&state.db, state.mailer.clone(), &state.settings.public_url, payload).await {
         Ok(()) => Json(R {
             success: true,
             data: None,
-            message: Some("注册成功".to_string()),
+            message: Some("注册成功，验证邮件已发送".to_string()),
             code: Some(200),
         }),
         Err(e) => {
@@ -48,3 +52,154 @@ pub async fn login(
     }
 }
 
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshTokenDTO>,
+) -> Json<R<TokenVO>> {
+    match user_service::refresh(&state.db, payload).await {
+        Ok(tokens) => Json(R {
+            success: true,
+            data: Some(tokens),
+            message: None,
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(401),
+        }),
+    }
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    AuthUser(claims): AuthUser,
+    payload: Option<Json<LogoutDTO>>,
+) -> Json<R<()>> {
+    match user_service::logout(&state.db, &claims, payload.map(|Json(p)| p)).await {
+        Ok(()) => Json(R {
+            success: true,
+            data: None,
+            message: Some("已退出登录".to_string()),
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(500),
+        }),
+    }
+}
+
+pub async fn change_password(
+    State(state): State<AppState>,
+    AuthUser(claims): AuthUser,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Json<R<TokenVO>> {
+    match user_service::change_password(&state.db, &claims, payload).await {
+        Ok(tokens) => Json(R {
+            success: true,
+            data: Some(tokens),
+            message: Some("密码修改成功".to_string()),
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(500),
+        }),
+    }
+}
+
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Json<R<()>> {
+    match user_service::request_password_reset(&state.db, state.mailer.clone(), &state.settings.public_url, payload).await {
+        Ok(()) => Json(R {
+            success: true,
+            data: None,
+            message: Some("如果该邮箱已注册，重置邮件已发送".to_string()),
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(500),
+        }),
+    }
+}
+
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ConfirmResetPasswordRequest>,
+) -> Json<R<()>> {
+    match user_service::reset_password(&state.db, payload).await {
+        Ok(()) => Json(R {
+            success: true,
+            data: None,
+            message: Some("密码已重置，请重新登录".to_string()),
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(400),
+        }),
+    }
+}
+
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Json<R<()>> {
+    match user_service::verify_email(&state.db, payload).await {
+        Ok(()) => Json(R {
+            success: true,
+            data: None,
+            message: Some("邮箱验证成功".to_string()),
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(400),
+        }),
+    }
+}
+
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    AuthUser(claims): AuthUser,
+) -> Json<R<()>> {
+    match user_service::resend_verification(&state.db, state.mailer.clone(), &state.settings.public_url, &claims).await {
+        Ok(()) => Json(R {
+            success: true,
+            data: None,
+            message: Some("验证邮件已发送".to_string()),
+            code: Some(200),
+        }),
+        Err(e) => Json(R {
+            success: false,
+            data: None,
+            message: Some(e.to_string()),
+            code: Some(400),
+        }),
+    }
+}
+
+pub async fn me(
+    State(state): State<AppState>,
+    AuthUser(claims): AuthUser,
+) -> Json<R<UserDetail>> {
+    match user_service::me(&state.db, &claims.sub).await {
+        Ok(detail) => Json(R { success: true, data: Some(detail), message: None, code: Some(200) }),
+        Err(e) => Json(R { success: false, data: None, message: Some(e.to_string()), code: Some(500) }),
+    }
+}
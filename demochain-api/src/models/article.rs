@@ -1,19 +1,122 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
 
 #[derive(Deserialize)]
 pub struct PageArticleDTO {
     pub page: Option<i64>,
     pub size: Option<i64>,
+    // 全文检索关键词，多个词以空格分隔
+    pub q: Option<String>,
+    // 按标签筛选（不区分大小写）
+    pub tag: Option<String>,
+    // 只返回指定字段，逗号分隔，如 fields=title,slug（id 总是返回）
+    pub fields: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Article {
     pub id: String,
+    pub slug: String,
     pub title: String,
     pub excerpt: String,
     pub content: String,
     pub tags: Vec<String>,
     pub views: i32,
     pub created: DateTime<Utc>,
-}
\ No newline at end of file
+    pub updated: DateTime<Utc>,
+    // format=html 时返回的渲染结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<RenderedContent>,
+}
+
+// 列表项：不含正文，正文只由文章详情接口返回
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArticleSummary {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub excerpt: String,
+    pub tags: Vec<String>,
+    pub views: i32,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    // 检索命中的高亮片段（已转义 HTML，命中词以 <mark> 包裹），仅检索时返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+// fields= 裁剪后的列表项：字段按固定顺序输出（serde_json 的 Map 默认按键名排序）
+pub struct SummaryFields(pub Vec<(&'static str, Value)>);
+
+impl Serialize for SummaryFields {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+// 列表项：未指定 fields= 时直接返回完整摘要，否则返回裁剪后的字段
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SummaryItem {
+    Full(ArticleSummary),
+    Selected(SummaryFields),
+}
+
+// 文章详情查询参数：format=html 时附带渲染后的 HTML、目录与阅读时长
+#[derive(Deserialize)]
+pub struct ArticleQuery {
+    pub format: Option<String>,
+}
+
+// Markdown 渲染结果（HTML 已清洗）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RenderedContent {
+    pub html: String,
+    pub toc: Vec<TocEntry>,
+    pub reading_minutes: u32,
+}
+
+// 目录项，id 与 HTML 中标题的锚点一致
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+}
+
+// 创建/修改文章
+#[derive(Debug, Deserialize)]
+pub struct ArticleDTO {
+    pub title: String,
+    pub excerpt: Option<String>,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // 不填时创建按标题生成，修改保持原 slug；改名后旧 slug 仍可访问
+    pub slug: Option<String>,
+}
+
+// 文章历史版本
+#[derive(Serialize, Debug)]
+pub struct ArticleRevisionVO {
+    pub id: String,
+    pub article_id: String,
+    pub title: String,
+    pub excerpt: String,
+    pub tags: Vec<String>,
+    pub editor_id: String,
+    pub created: DateTime<Utc>,
+}
+
+// 标签及文章数
+#[derive(Serialize, Debug)]
+pub struct TagVO {
+    pub name: String,
+    pub count: i64,
+}
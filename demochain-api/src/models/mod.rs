@@ -4,6 +4,8 @@ pub mod user;
 pub mod order;
 pub mod article;
 pub mod term;
+pub mod subscription;
+pub mod plan;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PageVO<T> {
@@ -1,10 +1,12 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 #[derive(Debug, Deserialize)]
 pub struct OrderDTO {
     pub plan_type: String,              // "monthly" | "yearly" | "lifetime"
     pub network: String,    // "usdt_trc20" | "usdt_erc20" | "usdt_bep20"
+    pub coupon: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -13,19 +15,145 @@ pub struct PageOrderDTO {
     pub size: Option<i64>,
 }
 
+// 管理端订单查询
+#[derive(Debug, Deserialize)]
+pub struct AdminOrderQuery {
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+    pub user_id: Option<String>,
+    pub state: Option<String>,
+    pub network: Option<String>,
+    pub q: Option<String>,
+}
+
+// 管理端未匹配转账查询：status=open（默认）| resolved
+#[derive(Debug, Deserialize)]
+pub struct UnmatchedTransferQuery {
+    pub page: Option<i64>,
+    pub size: Option<i64>,
+    pub status: Option<String>,
+}
+
+// 未能匹配订单的链上转账
+#[derive(Debug, Serialize)]
+pub struct UnmatchedTransferVO {
+    pub id: String,
+    pub network: String,
+    pub tx: String,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount: Decimal,
+    pub block_time: DateTime<Utc>,
+    // 该收款地址最近的订单，供对账参考
+    pub order_id: Option<String>,
+    pub source: String, // watcher | webhook
+    pub status: String, // open | resolved
+    pub note: Option<String>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created: DateTime<Utc>,
+}
+
+// 管理端操作原因（确认、退款、禁用账号、处理未匹配转账）
+#[derive(Debug, Deserialize)]
+pub struct AdminActionDTO {
+    pub reason: Option<String>,
+}
+
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OrderVO {
     pub id: String,
     pub user_id: String,
     pub plan_type: String,
-    pub amount: f64,
+    pub amount: Decimal, // 十进制字符串，如 "9.99"
     pub currency: String, // USDT
     pub network: String,
-    pub state: String, // created | paid | confirmed | expired | cancelled
+    pub state: String, // created | paid | confirmed | expired | cancelled | refunded
     pub address: String,
     pub sender_address: Option<String>,
     pub tx: Option<String>,
+    pub coupon_code: Option<String>,
+    pub discount: Option<Decimal>,
+    pub expires: Option<DateTime<Utc>>,
+    // 收款二维码内容
+    pub qr_payload: String,
     pub created: DateTime<Utc>,
     pub updated: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<OrderHistoryVO>,
+}
+
+/// 订单状态变更记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderHistoryVO {
+    pub from_state: String,
+    pub to_state: String,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+/// 订单详情查询；传入 state 与 wait 时进行长轮询，
+/// 订单状态变化或等待超时后返回
+#[derive(Debug, Deserialize)]
+pub struct OrderDetailQuery {
+    pub state: Option<String>,
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelOrderDTO {
+    pub reason: Option<String>,
+}
+
+/// 订单状态机
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    Created,
+    Paid,
+    Confirmed,
+    Expired,
+    Cancelled,
+    Refunded,
+}
+
+impl OrderState {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "created" => Some(Self::Created),
+            "paid" => Some(Self::Paid),
+            "confirmed" => Some(Self::Confirmed),
+            "expired" => Some(Self::Expired),
+            "cancelled" => Some(Self::Cancelled),
+            "refunded" => Some(Self::Refunded),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Paid => "paid",
+            Self::Confirmed => "confirmed",
+            Self::Expired => "expired",
+            Self::Cancelled => "cancelled",
+            Self::Refunded => "refunded",
+        }
+    }
+
+    /// 允许的状态迁移；已检测到付款（paid）的订单不再过期或取消，只能确认或退款
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use OrderState::*;
+        matches!(
+            (self, next),
+            (Created, Paid)
+                | (Created, Confirmed)
+                | (Created, Expired)
+                | (Created, Cancelled)
+                | (Paid, Confirmed)
+                | (Paid, Refunded)
+                | (Confirmed, Refunded)
+        )
+    }
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::subscription::PlanType;
+
+// 某套餐在某网络上的当前售价
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlanPriceVO {
+    pub network: String,
+    pub currency: String,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlanVO {
+    pub plan_type: PlanType,
+    pub vip: String,
+    pub prices: Vec<PlanPriceVO>,
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const VIP_FREE: &str = "1";
+
+// 套餐等级：数值越大权益越高，vip 编码与前端 useAccess 保持一致
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanType {
+    Monthly,
+    Yearly,
+    Lifetime,
+}
+
+impl PlanType {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "monthly" => Some(PlanType::Monthly),
+            "yearly" => Some(PlanType::Yearly),
+            "lifetime" => Some(PlanType::Lifetime),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanType::Monthly => "monthly",
+            PlanType::Yearly => "yearly",
+            PlanType::Lifetime => "lifetime",
+        }
+    }
+
+    pub fn vip(&self) -> &'static str {
+        match self {
+            PlanType::Monthly => "11",
+            PlanType::Yearly => "111",
+            PlanType::Lifetime => "1111",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntitlementVO {
+    pub plan_type: PlanType,
+    pub vip: String,
+    // 当前权益（含已续费的后续周期）的到期时间，永久套餐为 None
+    pub expires: Option<DateTime<Utc>>,
+}
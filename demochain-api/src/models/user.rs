@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use crate::models::subscription::EntitlementVO;
 
 #[derive(Debug, Deserialize)]
 pub struct LoginDTO {
@@ -13,19 +14,40 @@ pub struct RegisterDTO {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenDTO {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogoutDTO {
+    pub refresh_token: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AuthVO {
     pub token: String,
+    pub refresh_token: String,
     pub user_detail: UserDetail,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_in: Option<i64>,
 }
+
+#[derive(Debug, Serialize)]
+pub struct TokenVO {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     pub id: String,
     pub email: String,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub role: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
     pub created: DateTime<Utc>,
     pub updated: DateTime<Utc>,
 }
@@ -35,25 +57,32 @@ pub struct UserDetail {
     pub id: String,
     pub email: String,
     pub vip: String,
+    pub role: String,
     pub username: Option<String>,
+    pub email_verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entitlement: Option<EntitlementVO>,
     pub created: DateTime<Utc>,
     pub updated: DateTime<Utc>,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct ChangePasswordRequest {
     pub old_password: String,
     pub new_password: String,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct ResetPasswordRequest {
     pub email: String,
 }
 
-#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct ConfirmResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct VerifyEmailRequest {
     pub token: String,
@@ -66,14 +95,62 @@ pub struct Claims {
     pub email: String,
     pub exp: i64,    // expiration time
     pub iat: i64,    // issued at
+    // 签发时间（毫秒），与 token_valid_after 比较；旧令牌没有该字段时按 iat 所在秒的起点计
+    #[serde(default)]
+    pub iat_ms: i64,
+    pub jti: String, // token id, used by the revocation list
+    #[serde(default = "default_role")]
+    pub role: String, // user | admin
+}
+
+// 用户角色
+pub const ROLE_USER: &str = "user";
+pub const ROLE_EDITOR: &str = "editor";
+pub const ROLE_ADMIN: &str = "admin";
+
+fn default_role() -> String {
+    ROLE_USER.to_string()
 }
 
 impl Claims {
+    pub fn new(user_id: String, email: String, role: String, expires_in_seconds: i64) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            sub: user_id,
+            email,
+            role,
+            exp: now.timestamp() + expires_in_seconds,
+            iat: now.timestamp(),
+            iat_ms: now.timestamp_millis(),
+            jti: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// 签发时间（毫秒）
+    pub fn issued_at_millis(&self) -> i64 {
+        if self.iat_ms > 0 { self.iat_ms } else { self.iat * 1000 }
+    }
+}
+
+// 邮箱验证令牌 Claims（purpose 固定为 verify_email）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerifyClaims {
+    pub sub: String, // user id
+    pub email: String,
+    pub purpose: String,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+impl EmailVerifyClaims {
+    pub const PURPOSE: &'static str = "verify_email";
+
     pub fn new(user_id: String, email: String, expires_in_seconds: i64) -> Self {
         let now = chrono::Utc::now().timestamp();
         Self {
             sub: user_id,
             email,
+            purpose: Self::PURPOSE.to_string(),
             exp: now + expires_in_seconds,
             iat: now,
         }
@@ -1,9 +1,10 @@
 use crate::app::AppState;
 use crate::handlers;
-use axum::{routing::{get, post}, Router};
+use axum::{extract::DefaultBodyLimit, routing::{get, post}, Router};
 use axum::http::{HeaderValue, Method};
 use tower_http::cors::{AllowMethods, AllowOrigin, CorsLayer};
 use crate::handlers::health;
+use crate::service::webhook_service;
 
 // 配置 Axum 中间件（对任意状态类型的 Router 生效）
 pub fn configure_router<S>(router: Router<S>) -> Router<S>
@@ -43,8 +44,12 @@ pub fn build_router() -> Router<AppState> {
     Router::new()
         .merge(health_router())
         .merge(auth_router())
+        .merge(user_router())
         .merge(order_router())
+        .merge(plan_router())
+        .merge(payment_router())
         .merge(article_router())
+        .merge(admin_router())
         .merge(term_router())
 }
 
@@ -55,18 +60,61 @@ fn auth_router() -> Router<AppState> {
     Router::new()
         .route("/api/auth/register", post(handlers::user::register))
         .route("/api/auth/Login", post(handlers::user::login))
+        .route("/api/auth/refresh", post(handlers::user::refresh))
+        .route("/api/auth/logout", post(handlers::user::logout))
+        .route("/api/auth/reset-password/request", post(handlers::user::request_password_reset))
+        .route("/api/auth/reset-password/confirm", post(handlers::user::reset_password))
+        .route("/api/auth/verify-email", post(handlers::user::verify_email))
+        .route("/api/auth/verify-email/resend", post(handlers::user::resend_verification))
+}
+
+fn user_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/user/me", get(handlers::user::me))
+        .route("/api/user/password", post(handlers::user::change_password))
 }
 
 fn order_router() -> Router<AppState> {
     Router::new()
         .route("/api/order/add", post(handlers::order::add))
         .route("/api/order/page", get(handlers::order::page))
+        .route("/api/order/export", get(handlers::order::export))
+        .route("/api/order/:id", get(handlers::order::detail))
+        .route("/api/order/:id/cancel", post(handlers::order::cancel))
 }
 
+fn plan_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/plan/list", get(handlers::plan::list))
+}
+fn payment_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/payment/webhook/:provider",
+            post(handlers::payment::webhook).layer(DefaultBodyLimit::max(webhook_service::MAX_BODY_BYTES)),
+        )
+}
+fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/order/page", get(handlers::admin::order_page))
+        .route("/api/admin/order/:id/confirm", post(handlers::admin::confirm_order))
+        .route("/api/admin/order/:id/refund", post(handlers::admin::refund_order))
+        .route("/api/admin/payment/unmatched", get(handlers::admin::unmatched_transfers))
+        .route("/api/admin/payment/unmatched/:id/resolve", post(handlers::admin::resolve_unmatched_transfer))
+        .route("/api/admin/user/:id/disable", post(handlers::admin::disable_user))
+        .route("/api/admin/user/:id/enable", post(handlers::admin::enable_user))
+}
 fn article_router() -> Router<AppState> {
     Router::new()
         .route("/api/article/page", get(handlers::article::page))
+        .route("/api/article/tags", get(handlers::article::tags))
+        .route("/api/article/highlight.css", get(handlers::article::highlight_css))
+        .route("/api/article/add", post(handlers::article::add))
         .route("/api/article/:id", get(handlers::article::get_article))
+        .route("/api/article/:id/update", post(handlers::article::update))
+        .route("/api/article/:id/delete", post(handlers::article::delete))
+        .route("/api/article/:id/revisions", get(handlers::article::revisions))
+        .route("/api/article/:id/revisions/:revision_id/restore", post(handlers::article::restore))
 }
 
 fn term_router() -> Router<AppState> {
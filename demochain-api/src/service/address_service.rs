@@ -0,0 +1,162 @@
+use anyhow::{anyhow, bail, Context, Result};
+use bip32::XPub;
+use rust_decimal::Decimal;
+use sqlx::SqliteConnection;
+
+use crate::utils::{hd_wallet, money};
+
+/// 地址族：ERC20 / BEP20 共用 EVM 地址，TRC20 使用 TRON 地址
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainFamily {
+    Evm,
+    Tron,
+}
+
+impl ChainFamily {
+    pub fn of(network: &str) -> Option<Self> {
+        match network {
+            "usdt_erc20" | "usdt_bep20" => Some(ChainFamily::Evm),
+            "usdt_trc20" => Some(ChainFamily::Tron),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChainFamily::Evm => "evm",
+            ChainFamily::Tron => "tron",
+        }
+    }
+}
+
+pub struct AllocatedAddress {
+    pub address: String,
+    pub derivation_index: Option<i64>,
+}
+
+/// 收款地址分配：配置了 xpub 的地址族为每个订单派生独立地址，
+/// 否则退回固定收款地址。服务端只持有扩展公钥，私钥始终离线保存。
+pub struct AddressAllocator {
+    evm_xpub: Option<XPub>,
+    tron_xpub: Option<XPub>,
+    evm_fallback: Option<String>,
+    tron_fallback: Option<String>,
+}
+
+impl AddressAllocator {
+    pub fn new(
+        evm_xpub: Option<XPub>,
+        tron_xpub: Option<XPub>,
+        evm_fallback: Option<String>,
+        tron_fallback: Option<String>,
+    ) -> Self {
+        Self { evm_xpub, tron_xpub, evm_fallback, tron_fallback }
+    }
+
+    /// 为订单分配收款地址；应在插入订单的同一事务内调用
+    pub async fn allocate(&self, conn: &mut SqliteConnection, network: &str) -> Result<AllocatedAddress> {
+        let family = ChainFamily::of(network).ok_or_else(|| anyhow!("不支持的网络: {}", network))?;
+        let (xpub, fallback) = match family {
+            ChainFamily::Evm => (&self.evm_xpub, &self.evm_fallback),
+            ChainFamily::Tron => (&self.tron_xpub, &self.tron_fallback),
+        };
+
+        let Some(xpub) = xpub else {
+            return match fallback {
+                Some(address) => Ok(AllocatedAddress { address: address.clone(), derivation_index: None }),
+                None => bail!("该网络暂不支持收款"),
+            };
+        };
+
+        let chain = family.as_str();
+        let row = sqlx::query!(
+            r#"
+            UPDATE t_hd_index SET next_index = next_index + 1
+            WHERE chain = ?1
+            RETURNING next_index - 1 as "index!: i64"
+            "#,
+            chain
+        ).fetch_one(&mut *conn).await.context("分配派生索引失败")?;
+
+        let index = u32::try_from(row.index).context("派生索引溢出")?;
+        let hash = hd_wallet::derive_account_hash(xpub, index)?;
+        let address = match family {
+            ChainFamily::Evm => hd_wallet::evm_address(&hash),
+            ChainFamily::Tron => hd_wallet::tron_address(&hash),
+        };
+        Ok(AllocatedAddress { address, derivation_index: Some(row.index) })
+    }
+}
+
+/// 生成收款二维码内容：EVM 网络使用 EIP-681 代币转账 URI，TRC20 仅编码地址
+pub fn payment_uri(network: &str, address: &str, amount: Decimal) -> String {
+    // (USDT 合约地址, chain id)
+    let token = match network {
+        "usdt_erc20" => Some(("0xdAC17F958D2ee523a2206206994597C13D831ec7", 1)),
+        "usdt_bep20" => Some(("0x55d398326f99059fF775485246999027B3197955", 56)),
+        _ => None,
+    };
+    match (token, money::to_base_units(amount, network)) {
+        (Some((contract, chain_id)), Some(units)) => format!(
+            "ethereum:{}@{}/transfer?address={}&uint256={}",
+            contract, chain_id, address, units
+        ),
+        _ => address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hd_wallet::tests::{EVM_ADDRESS_0, EVM_XPUB, TRON_ADDRESS_0, TRON_XPUB};
+    use sqlx::Connection;
+
+    async fn hd_index_db() -> SqliteConnection {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE t_hd_index (chain TEXT PRIMARY KEY, next_index INTEGER NOT NULL DEFAULT 0);
+             INSERT INTO t_hd_index (chain, next_index) VALUES ('evm', 0), ('tron', 0);",
+        ).execute(&mut conn).await.unwrap();
+        conn
+    }
+
+    fn allocator() -> AddressAllocator {
+        AddressAllocator::new(
+            Some(hd_wallet::parse_xpub(EVM_XPUB).unwrap()),
+            Some(hd_wallet::parse_xpub(TRON_XPUB).unwrap()),
+            None,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn allocates_known_addresses_from_index_zero() {
+        let mut conn = hd_index_db().await;
+        let allocator = allocator();
+
+        let erc20 = allocator.allocate(&mut conn, "usdt_erc20").await.unwrap();
+        assert_eq!(erc20.address, EVM_ADDRESS_0);
+        assert_eq!(erc20.derivation_index, Some(0));
+
+        let trc20 = allocator.allocate(&mut conn, "usdt_trc20").await.unwrap();
+        assert_eq!(trc20.address, TRON_ADDRESS_0);
+        assert_eq!(trc20.derivation_index, Some(0));
+
+        // BEP20 与 ERC20 共用 EVM 索引，继续向后分配
+        let bep20 = allocator.allocate(&mut conn, "usdt_bep20").await.unwrap();
+        assert_eq!(bep20.derivation_index, Some(1));
+        assert_ne!(bep20.address, EVM_ADDRESS_0);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_fixed_address_without_xpub() {
+        let mut conn = hd_index_db().await;
+        let allocator = AddressAllocator::new(None, None, Some("0xfixed".into()), None);
+
+        let erc20 = allocator.allocate(&mut conn, "usdt_erc20").await.unwrap();
+        assert_eq!(erc20.address, "0xfixed");
+        assert_eq!(erc20.derivation_index, None);
+        assert!(allocator.allocate(&mut conn, "usdt_trc20").await.is_err());
+        assert!(allocator.allocate(&mut conn, "btc").await.is_err());
+    }
+}
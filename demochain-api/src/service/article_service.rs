@@ -1,111 +1,996 @@
-use sqlx::SqlitePool;
-use crate::models::article::Article;
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::models::article::{Article, ArticleDTO, ArticleRevisionVO, ArticleSummary, SummaryFields, TagVO};
 use crate::models::PageVO;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
+use serde_json::Value;
+
+// 字段长度上限（按字符计）
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_EXCERPT_CHARS: usize = 500;
+const MAX_CONTENT_CHARS: usize = 200_000;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_CHARS: usize = 32;
+const MAX_SLUG_CHARS: usize = 80;
+
+// 列表每页条数上限
+const MAX_PAGE_SIZE: i64 = 50;
+// 检索词个数上限；不足三个字的词无法走 trigram 索引，改为在有限的候选文章中子串匹配
+const MAX_SEARCH_TERMS: usize = 8;
+const MIN_INDEXED_TERM_CHARS: usize = 3;
+// 短词子串匹配的候选文章上限（最新发布的文章，或全文检索相关度最高的文章）
+const SHORT_TERM_SCAN_ROWS: i64 = 500;
+// 摘要片段长度与命中位置前保留的上下文（按字符计）；子串扫描只查找正文开头一段
+const SNIPPET_CHARS: usize = 120;
+const SNIPPET_LEAD_CHARS: usize = 30;
+const SNIPPET_SCAN_CHARS: usize = 10_000;
+
+// 列表可选字段（fields=），id 总是返回
+pub const SUMMARY_FIELDS: &[&str] = &["id", "slug", "title", "excerpt", "tags", "views", "created", "updated", "snippet"];
+
+// 文章表查询结果（详情）
+struct ArticleRow {
+    id: i64,
+    slug: String,
+    title: String,
+    excerpt: Option<String>,
+    content: Option<String>,
+    views: Option<i64>,
+    created: NaiveDateTime,
+    updated: NaiveDateTime,
+}
+
+impl ArticleRow {
+    fn into_article(self, tags: Vec<String>) -> Article {
+        Article {
+            id: self.id.to_string(),
+            slug: self.slug,
+            title: self.title,
+            excerpt: self.excerpt.unwrap_or_default(),
+            content: self.content.unwrap_or_default(),
+            tags,
+            views: self.views.unwrap_or(0) as i32,
+            created: self.created.and_utc(),
+            updated: self.updated.and_utc(),
+            rendered: None,
+        }
+    }
+}
+
+// 文章表查询结果（列表，不含正文）
+struct SummaryRow {
+    id: i64,
+    slug: String,
+    title: String,
+    excerpt: Option<String>,
+    views: Option<i64>,
+    created: NaiveDateTime,
+    updated: NaiveDateTime,
+}
+
+impl SummaryRow {
+    fn into_summary(self, tags: Vec<String>) -> ArticleSummary {
+        ArticleSummary {
+            id: self.id.to_string(),
+            slug: self.slug,
+            title: self.title,
+            excerpt: self.excerpt.unwrap_or_default(),
+            tags,
+            views: self.views.unwrap_or(0) as i32,
+            created: self.created.and_utc(),
+            updated: self.updated.and_utc(),
+            snippet: None,
+        }
+    }
+}
+
+/// 批量加载文章标签（按文章内顺序）
+async fn load_tags(db: &SqlitePool, article_ids: &[i64]) -> anyhow::Result<HashMap<i64, Vec<String>>> {
+    let ids = serde_json::to_string(article_ids)?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT at.article_id, t.name
+        FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+        WHERE at.article_id IN (SELECT value FROM json_each(?1))
+        ORDER BY at.article_id, at.position
+        "#,
+        ids
+    ).fetch_all(db).await.context("查询文章标签失败")?;
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        tags.entry(row.article_id).or_default().push(row.name);
+    }
+    Ok(tags)
+}
+
+async fn summaries(db: &SqlitePool, rows: Vec<SummaryRow>, include_tags: bool) -> anyhow::Result<Vec<ArticleSummary>> {
+    let mut tags = if include_tags {
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        load_tags(db, &ids).await?
+    } else {
+        HashMap::new()
+    };
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let t = tags.remove(&r.id).unwrap_or_default();
+            r.into_summary(t)
+        })
+        .collect())
+}
+
+async fn with_tags(db: &SqlitePool, rows: Vec<ArticleRow>) -> anyhow::Result<Vec<Article>> {
+    let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+    let mut tags = load_tags(db, &ids).await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let t = tags.remove(&r.id).unwrap_or_default();
+            r.into_article(t)
+        })
+        .collect())
+}
+
+/// 解析 fields= 参数；未指定时返回全部字段
+pub fn parse_fields(fields: Option<&str>) -> anyhow::Result<Option<Vec<String>>> {
+    let Some(fields) = fields.map(str::trim).filter(|f| !f.is_empty()) else {
+        return Ok(None);
+    };
+    let mut selected = vec!["id".to_string()];
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if field == "content" {
+            bail!("列表不返回正文，请使用文章详情接口");
+        }
+        if !SUMMARY_FIELDS.contains(&field) {
+            bail!("不支持的字段: {}，可选: {}", field, SUMMARY_FIELDS.join(","));
+        }
+        if !selected.iter().any(|f| f == field) {
+            selected.push(field.to_string());
+        }
+    }
+    Ok(Some(selected))
+}
 
-pub async fn page(db: &SqlitePool, page: i64, size: i64) -> Result<PageVO<Article>, sqlx::Error> {
-    let limit = if size <= 0 { 10 } else { size };
+/// 按 fields= 裁剪列表项，字段按 SUMMARY_FIELDS 顺序输出
+pub fn select_fields(items: Vec<ArticleSummary>, fields: &[String]) -> anyhow::Result<Vec<SummaryFields>> {
+    items
+        .into_iter()
+        .map(|item| {
+            let Value::Object(mut map) = serde_json::to_value(item)? else {
+                bail!("文章序列化失败");
+            };
+            Ok(SummaryFields(
+                SUMMARY_FIELDS
+                    .iter()
+                    .filter(|f| fields.iter().any(|s| s == *f))
+                    .filter_map(|f| map.remove(*f).map(|v| (*f, v)))
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
+/// 分页查询文章摘要；q 非空时按相关度排序并附带高亮片段，tag 非空时只返回带该标签的文章；
+/// include_tags / include_snippet 为 false 时（fields= 未选 tags / snippet）跳过标签查询 / 片段生成
+pub async fn page(
+    db: &SqlitePool,
+    page: i64,
+    size: i64,
+    q: Option<&str>,
+    tag: Option<&str>,
+    include_tags: bool,
+    include_snippet: bool,
+) -> anyhow::Result<PageVO<ArticleSummary>> {
+    let limit = if size <= 0 { 10 } else { size.min(MAX_PAGE_SIZE) };
     let page = if page <= 0 { 1 } else { page };
     let offset = (page - 1) * limit;
+    let tag = tag.map(str::trim).filter(|t| !t.is_empty());
+
+    let terms = search_terms(q.unwrap_or_default());
+    if !terms.is_empty() {
+        return search(db, &terms, tag, include_tags, include_snippet, page, limit).await;
+    }
 
     // 查询总数
     let total_row = sqlx::query!(
         r#"
-        SELECT COUNT(1) as "count!: i64" FROM t_article
-        "#
+        SELECT COUNT(1) as "count!: i64" FROM t_article a
+        WHERE a.deleted_at IS NULL
+          AND (?1 IS NULL OR EXISTS (
+            SELECT 1 FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+            WHERE at.article_id = a.id AND t.name = ?1
+          ))
+        "#,
+        tag
     ).fetch_one(db).await?;
 
     // 查询文章列表
-    let rows = sqlx::query!(
+    let rows = sqlx::query_as!(
+        SummaryRow,
         r#"
         SELECT 
-            id as "id!: i64",
-            title,
-            excerpt,
-            content,
-            tags,
-            views,
-            created as "created: NaiveDateTime"
-        FROM t_article
-        ORDER BY created DESC
-        LIMIT ?1 OFFSET ?2
+            a.id as "id!: i64",
+            ifnull(a.slug, CAST(a.id AS TEXT)) as "slug!: String",
+            a.title,
+            a.excerpt,
+            a.views,
+            a.created as "created: NaiveDateTime",
+            a.updated as "updated!: NaiveDateTime"
+        FROM t_article a
+        WHERE a.deleted_at IS NULL
+          AND (?1 IS NULL OR EXISTS (
+            SELECT 1 FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+            WHERE at.article_id = a.id AND t.name = ?1
+          ))
+        ORDER BY a.created DESC
+        LIMIT ?2 OFFSET ?3
         "#,
+        tag,
         limit,
         offset
     ).fetch_all(db).await?;
 
-    let mut articles = Vec::with_capacity(rows.len());
-    for row in rows {
-        // 解析 JSON 格式的 tags
-        let tags: Vec<String> = match row.tags {
-            Some(tags_json) => {
-                serde_json::from_str(&tags_json).unwrap_or_else(|_| vec![])
-            }
-            None => vec![]
-        };
-
-        let article = Article {
-            id: row.id.to_string(),
-            title: row.title,
-            excerpt: row.excerpt.unwrap_or_default(),
-            content: row.content.unwrap_or_default(),
-            tags,
-            views: row.views.unwrap_or(0) as i32,
-            created: row.created.and_utc(),
-        };
-        articles.push(article);
-    }
-
     Ok(PageVO {
-        items: articles,
+        items: summaries(db, rows, include_tags).await?,
         total: total_row.count,
         page,
         size: limit,
     })
 }
 
-pub async fn get_by_id(db: &SqlitePool, id: &str) -> Result<Option<Article>, sqlx::Error> {
+/// 拆分检索词：按空白切分、去重，最多保留 MAX_SEARCH_TERMS 个
+fn search_terms(q: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in q.split_whitespace() {
+        let term = fold_case(term);
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+        if terms.len() == MAX_SEARCH_TERMS {
+            break;
+        }
+    }
+    terms
+}
+
+/// 全文检索：三字及以上的词组成 FTS5 查询（按 bm25 排序，标题权重最高），
+/// 更短的词要求在标题、摘要或标签中作为子串出现；所有词之间为 AND。
+/// 含短词时只在 SHORT_TERM_SCAN_ROWS 篇候选文章内匹配，总数也以此为上限
+async fn search(
+    db: &SqlitePool,
+    terms: &[String],
+    tag: Option<&str>,
+    include_tags: bool,
+    include_snippet: bool,
+    page: i64,
+    limit: i64,
+) -> anyhow::Result<PageVO<ArticleSummary>> {
+    let offset = (page - 1) * limit;
+    let (indexed, short): (Vec<&String>, Vec<&String>) =
+        terms.iter().partition(|t| t.chars().count() >= MIN_INDEXED_TERM_CHARS);
+    // 每个词作为短语并转义双引号，避免用户输入被当作 FTS5 语法
+    let query = indexed
+        .iter()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let (total, rows) = if short.is_empty() {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(1) as "count!: i64"
+            FROM t_article_fts f JOIN t_article a ON a.id = f.rowid
+            WHERE t_article_fts MATCH ?1 AND a.deleted_at IS NULL
+              AND (?2 IS NULL OR EXISTS (
+                SELECT 1 FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+                WHERE at.article_id = a.id AND t.name = ?2
+              ))
+            "#,
+            query,
+            tag
+        ).fetch_one(db).await.context("检索文章失败")?;
+        let rows = sqlx::query_as!(
+            SummaryRow,
+            r#"
+            SELECT
+                a.id as "id!: i64",
+                ifnull(a.slug, CAST(a.id AS TEXT)) as "slug!: String",
+                a.title,
+                a.excerpt,
+                a.views,
+                a.created as "created: NaiveDateTime",
+                a.updated as "updated!: NaiveDateTime"
+            FROM t_article_fts f JOIN t_article a ON a.id = f.rowid
+            WHERE t_article_fts MATCH ?1 AND a.deleted_at IS NULL
+              AND (?2 IS NULL OR EXISTS (
+                SELECT 1 FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+                WHERE at.article_id = a.id AND t.name = ?2
+              ))
+            ORDER BY bm25(t_article_fts, 10.0, 5.0, 1.0, 3.0)
+            LIMIT ?3 OFFSET ?4
+            "#,
+            query,
+            tag,
+            limit,
+            offset
+        ).fetch_all(db).await.context("检索文章失败")?;
+        (total, rows)
+    } else {
+        // 候选文章按顺序取出后在服务端匹配短词，大小写折叠方式与检索词一致
+        let candidates = if indexed.is_empty() {
+            sqlx::query_as!(
+                ShortTermCandidate,
+                r#"
+                SELECT a.id as "id!: i64", a.title, a.excerpt, a.tags
+                FROM t_article a
+                WHERE a.deleted_at IS NULL
+                  AND (?1 IS NULL OR EXISTS (
+                    SELECT 1 FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+                    WHERE at.article_id = a.id AND t.name = ?1
+                  ))
+                ORDER BY a.created DESC
+                LIMIT ?2
+                "#,
+                tag,
+                SHORT_TERM_SCAN_ROWS
+            ).fetch_all(db).await
+        } else {
+            sqlx::query_as!(
+                ShortTermCandidate,
+                r#"
+                SELECT a.id as "id!: i64", a.title, a.excerpt, a.tags
+                FROM t_article_fts f JOIN t_article a ON a.id = f.rowid
+                WHERE t_article_fts MATCH ?1 AND a.deleted_at IS NULL
+                  AND (?2 IS NULL OR EXISTS (
+                    SELECT 1 FROM t_article_tag at JOIN t_tag t ON t.id = at.tag_id
+                    WHERE at.article_id = a.id AND t.name = ?2
+                  ))
+                ORDER BY bm25(t_article_fts, 10.0, 5.0, 1.0, 3.0)
+                LIMIT ?3
+                "#,
+                query,
+                tag,
+                SHORT_TERM_SCAN_ROWS
+            ).fetch_all(db).await
+        }.context("检索文章失败")?;
+
+        let matched: Vec<i64> = candidates
+            .iter()
+            .filter(|c| c.matches(&short))
+            .map(|c| c.id)
+            .collect();
+        let page_ids: Vec<i64> = matched.iter().copied().skip(offset as usize).take(limit as usize).collect();
+        (matched.len() as i64, load_summary_rows(db, &page_ids).await?)
+    };
+
+    let mut snippets = if !include_snippet {
+        HashMap::new()
+    } else if indexed.is_empty() {
+        // 短词无索引可用，取当前页正文自行定位命中位置
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        let mut contents = load_contents(db, &ids).await?;
+        rows.iter()
+            .map(|r| {
+                let content = contents.remove(&r.id).unwrap_or_default();
+                (r.id, snippet(&content, r.excerpt.as_deref().unwrap_or_default(), &r.title, terms))
+            })
+            .collect()
+    } else {
+        let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+        load_snippets(db, &query, &ids).await?
+    };
+
+    let items = summaries(db, rows, include_tags)
+        .await?
+        .into_iter()
+        .map(|mut article| {
+            if include_snippet {
+                article.snippet = Some(article.id.parse().ok().and_then(|id| snippets.remove(&id)).unwrap_or_default());
+            }
+            article
+        })
+        .collect();
+    Ok(PageVO { items, total, page, size: limit })
+}
+
+// 短词匹配的候选文章（不含正文）
+struct ShortTermCandidate {
+    id: i64,
+    title: String,
+    excerpt: Option<String>,
+    tags: Option<String>,
+}
+
+impl ShortTermCandidate {
+    fn matches(&self, terms: &[&String]) -> bool {
+        let text = fold_case(&format!(
+            "{} {} {}",
+            self.title,
+            self.excerpt.as_deref().unwrap_or_default(),
+            self.tags.as_deref().unwrap_or_default()
+        ));
+        terms.iter().all(|t| text.contains(t.as_str()))
+    }
+}
+
+/// 按给定顺序加载文章摘要行
+async fn load_summary_rows(db: &SqlitePool, article_ids: &[i64]) -> anyhow::Result<Vec<SummaryRow>> {
+    let ids = serde_json::to_string(article_ids)?;
+    sqlx::query_as!(
+        SummaryRow,
+        r#"
+        SELECT
+            a.id as "id!: i64",
+            ifnull(a.slug, CAST(a.id AS TEXT)) as "slug!: String",
+            a.title,
+            a.excerpt,
+            a.views,
+            a.created as "created: NaiveDateTime",
+            a.updated as "updated!: NaiveDateTime"
+        FROM json_each(?1) j JOIN t_article a ON a.id = j.value
+        ORDER BY j.key
+        "#,
+        ids
+    ).fetch_all(db).await.context("检索文章失败")
+}
+
+async fn load_snippets(db: &SqlitePool, query: &str, article_ids: &[i64]) -> anyhow::Result<HashMap<i64, String>> {
+    let ids = serde_json::to_string(article_ids)?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT rowid as "id!: i64", snippet(t_article_fts, -1, char(2), char(3), '…', 64) as "snippet: String"
+        FROM t_article_fts
+        WHERE t_article_fts MATCH ?1 AND rowid IN (SELECT value FROM json_each(?2))
+        "#,
+        query,
+        ids
+    ).fetch_all(db).await.context("检索文章失败")?;
+    Ok(rows.into_iter().map(|r| (r.id, mark_snippet(&r.snippet.unwrap_or_default()))).collect())
+}
+
+fn mark_snippet(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut open = false;
+    for c in raw.chars() {
+        match c {
+            '\u{2}' if !open => {
+                out.push_str("<mark>");
+                open = true;
+            }
+            '\u{3}' if open => {
+                out.push_str("</mark>");
+                open = false;
+            }
+            '\u{2}' | '\u{3}' => {}
+            c => escape_into(&mut out, c),
+        }
+    }
+    if open {
+        out.push_str("</mark>");
+    }
+    out
+}
+
+async fn load_contents(db: &SqlitePool, article_ids: &[i64]) -> anyhow::Result<HashMap<i64, String>> {
+    let ids = serde_json::to_string(article_ids)?;
+    let rows = sqlx::query!(
+        r#"SELECT id as "id!: i64", content FROM t_article WHERE id IN (SELECT value FROM json_each(?1))"#,
+        ids
+    ).fetch_all(db).await.context("检索文章失败")?;
+    Ok(rows.into_iter().map(|r| (r.id, r.content.unwrap_or_default())).collect())
+}
+
+/// 高亮片段（子串扫描路径）：取正文开头一段（其次摘要、标题）中第一个命中位置附近的一段文字，
+/// 转义 HTML 后用 <mark> 包裹所有命中的检索词
+fn snippet(content: &str, excerpt: &str, title: &str, terms: &[String]) -> String {
+    let patterns: Vec<Vec<char>> = terms.iter().filter(|t| !t.is_empty()).map(|t| t.chars().collect()).collect();
+    // 返回原文字符、逐字符小写后的字符，以及是否被截断
+    let window = |text: &str| -> (Vec<char>, Vec<char>, bool) {
+        let mut chars: Vec<char> = text.chars().take(SNIPPET_SCAN_CHARS + 1).collect();
+        let truncated = chars.len() > SNIPPET_SCAN_CHARS;
+        chars.truncate(SNIPPET_SCAN_CHARS);
+        let lower = chars.iter().map(|c| lower_char(*c)).collect();
+        (chars, lower, truncated)
+    };
+    let hit = [content, excerpt, title].iter().find_map(|text| {
+        let (chars, lower, truncated) = window(text);
+        let haystack: String = lower.iter().collect();
+        let pos = terms.iter().filter(|t| !t.is_empty()).filter_map(|t| haystack.find(t.as_str())).min()?;
+        let at = haystack[..pos].chars().count();
+        Some((chars, lower, truncated, at.saturating_sub(SNIPPET_LEAD_CHARS)))
+    });
+    let (chars, lower, truncated, start) = hit.unwrap_or_else(|| {
+        let (chars, lower, truncated) = window(content);
+        (chars, lower, truncated, 0)
+    });
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        match match_at(&lower, i, &patterns) {
+            Some(len) => {
+                let stop = (i + len).min(end);
+                out.push_str("<mark>");
+                chars[i..stop].iter().for_each(|c| escape_into(&mut out, *c));
+                out.push_str("</mark>");
+                i = stop;
+            }
+            None => {
+                escape_into(&mut out, chars[i]);
+                i += 1;
+            }
+        }
+    }
+    if end < chars.len() || truncated {
+        out.push('…');
+    }
+    out
+}
+
+// 返回在 i 处命中的最长检索词长度（按字符计）
+fn match_at(lower: &[char], i: usize, patterns: &[Vec<char>]) -> Option<usize> {
+    patterns
+        .iter()
+        .filter(|p| lower[i..].starts_with(p))
+        .map(|p| p.len())
+        .max()
+}
+
+/// 检索用的大小写折叠：逐字符 Unicode 小写，检索词、候选文本与高亮定位共用
+fn fold_case(s: &str) -> String {
+    s.chars().map(lower_char).collect()
+}
+
+// 按字符小写（多字符展开的情况保留原字符，保证下标与原文一一对应）
+fn lower_char(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn escape_into(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#39;"),
+        c => out.push(c),
+    }
+}
+
+pub async fn get_by_id(db: &SqlitePool, id: &str) -> anyhow::Result<Option<Article>> {
     let id_num: i64 = match id.parse() {
         Ok(num) => num,
         Err(_) => return Ok(None),
     };
 
-    let row = sqlx::query!(
+    let row = sqlx::query_as!(
+        ArticleRow,
         r#"
         SELECT 
             id as "id!: i64",
+            ifnull(slug, CAST(id AS TEXT)) as "slug!: String",
             title,
             excerpt,
             content,
-            tags,
             views,
-            created as "created: NaiveDateTime"
+            created as "created: NaiveDateTime",
+            updated as "updated!: NaiveDateTime"
         FROM t_article
-        WHERE id = ?1
+        WHERE id = ?1 AND deleted_at IS NULL
         "#,
         id_num
     ).fetch_optional(db).await?;
 
     match row {
-        Some(row) => {
-            let tags: Vec<String> = match row.tags {
-                Some(tags_json) => {
-                    serde_json::from_str(&tags_json).unwrap_or_else(|_| vec![])
-                }
-                None => vec![]
-            };
+        Some(r) => Ok(with_tags(db, vec![r]).await?.pop()),
+        None => Ok(None),
+    }
+}
 
-            let article = Article {
-                id: row.id.to_string(),
-                title: row.title,
-                excerpt: row.excerpt.unwrap_or_default(),
-                content: row.content.unwrap_or_default(),
-                tags,
-                views: row.views.unwrap_or(0) as i32,
-                created: row.created.and_utc(),
-            };
-            Ok(Some(article))
+/// 按 ID 或 slug 查询文章的结果
+pub enum ArticleLookup {
+    Found(Box<Article>),
+    // 旧 slug，指向文章当前的 slug
+    Moved(String),
+    NotFound,
+}
+
+/// 纯数字按 ID 查询，否则按 slug 查询；命中改名前的旧 slug 时返回当前 slug 供重定向
+pub async fn lookup(db: &SqlitePool, id_or_slug: &str) -> anyhow::Result<ArticleLookup> {
+    if id_or_slug.parse::<i64>().is_ok() {
+        return Ok(get_by_id(db, id_or_slug).await?.map_or(ArticleLookup::NotFound, |a| ArticleLookup::Found(Box::new(a))));
+    }
+
+    let row = sqlx::query_as!(
+        ArticleRow,
+        r#"
+        SELECT
+            id as "id!: i64",
+            slug as "slug!: String",
+            title,
+            excerpt,
+            content,
+            views,
+            created as "created: NaiveDateTime",
+            updated as "updated!: NaiveDateTime"
+        FROM t_article
+        WHERE slug = ?1 AND deleted_at IS NULL
+        "#,
+        id_or_slug
+    ).fetch_optional(db).await.context("查询文章失败")?;
+    if let Some(r) = row {
+        return Ok(with_tags(db, vec![r]).await?.pop().map_or(ArticleLookup::NotFound, |a| ArticleLookup::Found(Box::new(a))));
+    }
+
+    let current = sqlx::query_scalar!(
+        r#"
+        SELECT a.slug as "slug!: String"
+        FROM t_article_slug_alias s JOIN t_article a ON a.id = s.article_id
+        WHERE s.slug = ?1 AND a.deleted_at IS NULL AND a.slug IS NOT NULL
+        "#,
+        id_or_slug
+    ).fetch_optional(db).await.context("查询文章失败")?;
+    Ok(current.map_or(ArticleLookup::NotFound, ArticleLookup::Moved))
+}
+
+// 校验并规整后的文章字段
+struct ArticleFields {
+    title: String,
+    excerpt: Option<String>,
+    content: String,
+    tags: Vec<String>,
+    // 编辑指定的 slug；未指定时创建按标题生成，修改保持不变
+    slug: Option<String>,
+}
+
+fn validate(payload: ArticleDTO) -> anyhow::Result<ArticleFields> {
+    let slug = match payload.slug.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(s) => {
+            let slug = slug_base(s);
+            if slug.is_empty() || slug.chars().all(|c| c.is_ascii_digit()) {
+                bail!("slug 只能包含字母、数字和连字符，且不能是纯数字");
+            }
+            Some(slug)
         }
-        None => Ok(None),
+        None => None,
+    };
+    let title = payload.title.trim().to_string();
+    if title.is_empty() {
+        bail!("标题不能为空");
+    }
+    if title.chars().count() > MAX_TITLE_CHARS {
+        bail!("标题不能超过 {} 个字符", MAX_TITLE_CHARS);
+    }
+    let excerpt = payload.excerpt.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    if excerpt.as_ref().is_some_and(|e| e.chars().count() > MAX_EXCERPT_CHARS) {
+        bail!("摘要不能超过 {} 个字符", MAX_EXCERPT_CHARS);
     }
-}
\ No newline at end of file
+    if payload.content.trim().is_empty() {
+        bail!("正文不能为空");
+    }
+    if payload.content.chars().count() > MAX_CONTENT_CHARS {
+        bail!("正文不能超过 {} 个字符", MAX_CONTENT_CHARS);
+    }
+    // 标签去空白、去重（不区分大小写）并保持原顺序
+    let mut tags: Vec<String> = Vec::new();
+    for tag in payload.tags {
+        let tag = tag.trim().to_string();
+        if tag.is_empty() || tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            bail!("标签不能超过 {} 个字符", MAX_TAG_CHARS);
+        }
+        tags.push(tag);
+    }
+    if tags.len() > MAX_TAGS {
+        bail!("标签不能超过 {} 个", MAX_TAGS);
+    }
+    Ok(ArticleFields {
+        title,
+        excerpt,
+        content: payload.content,
+        tags,
+        slug,
+    })
+}
+
+/// 由文本生成 slug：转写为小写拼音/字母并以连字符连接，截断到 MAX_SLUG_CHARS
+fn slug_base(text: &str) -> String {
+    let slug = slug::slugify(text);
+    if slug.len() <= MAX_SLUG_CHARS {
+        return slug;
+    }
+    // slug 只含 ASCII，可按字节截断；尽量在连字符处断开
+    let cut = &slug[..MAX_SLUG_CHARS];
+    cut.rsplit_once('-').map_or(cut, |(head, _)| head).trim_end_matches('-').to_string()
+}
+
+/// 设置文章 slug：重名时追加 -2、-3…；无法生成或为纯数字（会与 ID 混淆）时用 article-<ID>。
+/// 原 slug 保留为别名，旧链接继续可用
+async fn set_slug(conn: &mut SqliteConnection, article_id: i64, text: &str) -> anyhow::Result<String> {
+    let base = slug_base(text);
+    let base = if base.is_empty() || base.chars().all(|c| c.is_ascii_digit()) {
+        format!("article-{}", article_id)
+    } else {
+        base
+    };
+
+    let mut slug = base.clone();
+    let mut n = 1;
+    loop {
+        let taken = sqlx::query_scalar!(
+            r#"
+            SELECT (EXISTS (SELECT 1 FROM t_article WHERE slug = ?1 AND id <> ?2)
+                 OR EXISTS (SELECT 1 FROM t_article_slug_alias WHERE slug = ?1 AND article_id <> ?2)) as "taken!: bool"
+            "#,
+            slug,
+            article_id
+        ).fetch_one(&mut *conn).await.context("生成 slug 失败")?;
+        if !taken {
+            break;
+        }
+        n += 1;
+        slug = format!("{}-{}", base, n);
+    }
+
+    let current = sqlx::query_scalar!("SELECT slug FROM t_article WHERE id = ?1", article_id)
+        .fetch_one(&mut *conn).await.context("生成 slug 失败")?;
+    if current.as_deref() == Some(slug.as_str()) {
+        return Ok(slug);
+    }
+    let now = Utc::now().naive_utc();
+    if let Some(old) = current {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO t_article_slug_alias (slug, article_id, created) VALUES (?1, ?2, ?3)",
+            old,
+            article_id,
+            now
+        ).execute(&mut *conn).await.context("保存 slug 别名失败")?;
+    }
+    // 改回曾用过的 slug 时，它不再是别名
+    sqlx::query!("DELETE FROM t_article_slug_alias WHERE slug = ?1", slug)
+        .execute(&mut *conn).await.context("保存 slug 失败")?;
+    sqlx::query!("UPDATE t_article SET slug = ?1 WHERE id = ?2", slug, article_id)
+        .execute(&mut *conn).await.context("保存 slug 失败")?;
+    Ok(slug)
+}
+
+/// 为迁移前没有 slug 的文章按标题补齐（启动时调用）
+pub async fn backfill_slugs(db: &SqlitePool) -> anyhow::Result<usize> {
+    let rows = sqlx::query!(r#"SELECT id as "id!: i64", title FROM t_article WHERE slug IS NULL"#)
+        .fetch_all(db).await.context("查询文章失败")?;
+    for row in &rows {
+        let mut tx = db.begin().await.context("开启事务失败")?;
+        set_slug(&mut tx, row.id, &row.title).await?;
+        tx.commit().await.context("提交事务失败")?;
+    }
+    Ok(rows.len())
+}
+
+/// 重写文章的标签关联，同时更新 t_article.tags 冗余副本（供全文检索与版本快照）
+async fn set_tags(conn: &mut SqliteConnection, article_id: i64, tags: &[String]) -> anyhow::Result<()> {
+    let tags_json = serde_json::to_string(tags)?;
+    sqlx::query!("UPDATE t_article SET tags = ?1 WHERE id = ?2", tags_json, article_id)
+        .execute(&mut *conn).await.context("保存文章标签失败")?;
+    sqlx::query!("DELETE FROM t_article_tag WHERE article_id = ?1", article_id)
+        .execute(&mut *conn).await.context("保存文章标签失败")?;
+    for (position, name) in tags.iter().enumerate() {
+        let position = position as i64;
+        sqlx::query!("INSERT OR IGNORE INTO t_tag (name) VALUES (?1)", name)
+            .execute(&mut *conn).await.context("保存文章标签失败")?;
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO t_article_tag (article_id, tag_id, position)
+            SELECT ?1, id, ?2 FROM t_tag WHERE name = ?3
+            "#,
+            article_id,
+            position,
+            name
+        ).execute(&mut *conn).await.context("保存文章标签失败")?;
+    }
+    Ok(())
+}
+
+/// 以文章当前内容保存一个版本
+async fn snapshot(conn: &mut SqliteConnection, article_id: i64, editor_id: &str, now: NaiveDateTime) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO t_article_revision (article_id, title, excerpt, content, tags, editor_id, created)
+        SELECT id, title, excerpt, content, tags, ?2, ?3 FROM t_article WHERE id = ?1
+        "#,
+        article_id,
+        editor_id,
+        now
+    ).execute(&mut *conn).await.context("保存文章版本失败")?;
+    Ok(())
+}
+
+// 编辑接口返回摘要，正文只由文章详情接口返回
+async fn reload(db: &SqlitePool, id: i64) -> anyhow::Result<ArticleSummary> {
+    let row = sqlx::query_as!(
+        SummaryRow,
+        r#"
+        SELECT
+            id as "id!: i64",
+            ifnull(slug, CAST(id AS TEXT)) as "slug!: String",
+            title,
+            excerpt,
+            views,
+            created as "created: NaiveDateTime",
+            updated as "updated!: NaiveDateTime"
+        FROM t_article
+        WHERE id = ?1 AND deleted_at IS NULL
+        "#,
+        id
+    ).fetch_optional(db).await.context("查询文章失败")?.context("文章不存在")?;
+    summaries(db, vec![row], true).await?.pop().context("文章不存在")
+}
+
+pub async fn create(db: &SqlitePool, editor_id: &str, payload: ArticleDTO) -> anyhow::Result<ArticleSummary> {
+    let fields = validate(payload)?;
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await.context("开启事务失败")?;
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO t_article (title, excerpt, content, views, author_id, created, updated)
+        VALUES (?1, ?2, ?3, 0, ?4, ?5, ?5)
+        RETURNING id as "id!: i64"
+        "#,
+        fields.title,
+        fields.excerpt,
+        fields.content,
+        editor_id,
+        now
+    ).fetch_one(&mut *tx).await.context("创建文章失败")?;
+    set_tags(&mut tx, id, &fields.tags).await?;
+    set_slug(&mut tx, id, fields.slug.as_deref().unwrap_or(&fields.title)).await?;
+    snapshot(&mut tx, id, editor_id, now).await?;
+    tx.commit().await.context("提交事务失败")?;
+    reload(db, id).await
+}
+
+pub async fn update(db: &SqlitePool, editor_id: &str, id: i64, payload: ArticleDTO) -> anyhow::Result<ArticleSummary> {
+    let fields = validate(payload)?;
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await.context("开启事务失败")?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE t_article SET title = ?1, excerpt = ?2, content = ?3, updated = ?4
+        WHERE id = ?5 AND deleted_at IS NULL
+        "#,
+        fields.title,
+        fields.excerpt,
+        fields.content,
+        now,
+        id
+    ).execute(&mut *tx).await.context("修改文章失败")?;
+    if result.rows_affected() == 0 {
+        bail!("文章不存在");
+    }
+    set_tags(&mut tx, id, &fields.tags).await?;
+    if let Some(slug) = &fields.slug {
+        set_slug(&mut tx, id, slug).await?;
+    }
+    snapshot(&mut tx, id, editor_id, now).await?;
+    tx.commit().await.context("提交事务失败")?;
+    reload(db, id).await
+}
+
+/// 软删除：文章不再出现在列表和详情中，版本记录保留
+pub async fn delete(db: &SqlitePool, id: i64) -> anyhow::Result<()> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query!(
+        "UPDATE t_article SET deleted_at = ?1, updated = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        now,
+        id
+    ).execute(db).await.context("删除文章失败")?;
+    if result.rows_affected() == 0 {
+        bail!("文章不存在");
+    }
+    Ok(())
+}
+
+/// 文章的全部历史版本（不含正文），最新的在前
+pub async fn revisions(db: &SqlitePool, id: i64) -> anyhow::Result<Vec<ArticleRevisionVO>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id as "id!: i64",
+            article_id,
+            title,
+            excerpt,
+            tags,
+            editor_id,
+            created as "created: NaiveDateTime"
+        FROM t_article_revision
+        WHERE article_id = ?1
+        ORDER BY id DESC
+        "#,
+        id
+    ).fetch_all(db).await.context("查询文章版本失败")?;
+    Ok(rows.into_iter().map(|row| ArticleRevisionVO {
+        id: row.id.to_string(),
+        article_id: row.article_id.to_string(),
+        title: row.title,
+        excerpt: row.excerpt.unwrap_or_default(),
+        tags: row.tags.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default(),
+        editor_id: row.editor_id,
+        created: row.created.and_utc(),
+    }).collect())
+}
+
+/// 恢复到指定版本；恢复本身也会保存为一个新版本，历史不会被改写
+pub async fn restore(db: &SqlitePool, editor_id: &str, id: i64, revision_id: i64) -> anyhow::Result<ArticleSummary> {
+    let now = Utc::now().naive_utc();
+    let mut tx = db.begin().await.context("开启事务失败")?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE t_article SET (title, excerpt, content, tags) = (
+            SELECT title, excerpt, content, tags FROM t_article_revision WHERE id = ?1 AND article_id = ?2
+        ), updated = ?3
+        WHERE id = ?2 AND deleted_at IS NULL
+          AND EXISTS (SELECT 1 FROM t_article_revision WHERE id = ?1 AND article_id = ?2)
+        "#,
+        revision_id,
+        id,
+        now
+    ).execute(&mut *tx).await.context("恢复文章失败")?;
+    if result.rows_affected() == 0 {
+        bail!("文章或版本不存在");
+    }
+    // 版本中的标签是 JSON 快照，恢复时同步重建标签关联
+    let tags_json = sqlx::query_scalar!("SELECT tags FROM t_article WHERE id = ?1", id)
+        .fetch_one(&mut *tx).await.context("恢复文章失败")?;
+    let tags: Vec<String> = tags_json.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default();
+    set_tags(&mut tx, id, &tags).await?;
+    snapshot(&mut tx, id, editor_id, now).await?;
+    tx.commit().await.context("提交事务失败")?;
+    reload(db, id).await
+}
+
+/// 标签及其未删除文章数，按文章数降序
+pub async fn tags(db: &SqlitePool) -> anyhow::Result<Vec<TagVO>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT t.name, COUNT(a.id) as "count!: i64"
+        FROM t_tag t
+        JOIN t_article_tag at ON at.tag_id = t.id
+        JOIN t_article a ON a.id = at.article_id AND a.deleted_at IS NULL
+        GROUP BY t.id
+        ORDER BY 2 DESC, t.name
+        "#
+    ).fetch_all(db).await.context("查询标签失败")?;
+    Ok(rows.into_iter().map(|r| TagVO { name: r.name, count: r.count }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_terms_fold_case_like_search_terms() {
+        let candidate = ShortTermCandidate {
+            id: 1,
+            title: "ÉTÉ Notes".into(),
+            excerpt: Some("区块链入门".into()),
+            tags: Some(r#"["DeFi"]"#.into()),
+        };
+        let terms = search_terms("ét 入门 de");
+        assert_eq!(terms, vec!["ét", "入门", "de"]);
+        assert!(candidate.matches(&terms.iter().collect::<Vec<_>>()));
+
+        let missing = search_terms("ét zz");
+        assert!(!candidate.matches(&missing.iter().collect::<Vec<_>>()));
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Context, Result};
+use rust_decimal::Decimal;
+use sqlx::SqliteConnection;
+
+use crate::utils::money;
+
+/// 待确定应付金额的订单（已插入、尚未提交）
+pub struct PendingOrder<'a> {
+    pub id: i64,
+    pub network: &'a str,
+    pub address: &'a str,
+    pub amount: Decimal,
+    // 派生地址的索引，None 表示共享收款地址
+    pub derivation_index: Option<i64>,
+}
+
+/// 付款归属策略：决定链上转账如何对应到订单。
+/// 对账时按 (网络, 地址, 金额) 精确匹配，策略负责在下单时保证该组合能区分订单。
+#[axum::async_trait]
+pub trait AttributionStrategy: Send + Sync {
+    /// 确定订单最终应付金额；在插入订单的同一事务内调用
+    async fn assign(&self, conn: &mut SqliteConnection, order: &PendingOrder<'_>) -> Result<Decimal>;
+}
+
+/// 按收款地址归属：应付金额即标价，依赖每个订单独立的派生地址
+pub struct AddressAttribution;
+
+#[axum::async_trait]
+impl AttributionStrategy for AddressAttribution {
+    async fn assign(&self, _conn: &mut SqliteConnection, order: &PendingOrder<'_>) -> Result<Decimal> {
+        Ok(order.amount)
+    }
+}
+
+/// 按唯一金额归属：共享收款地址的订单在标价上加 slot * step 的尾差，
+/// 同一地址上的待支付订单金额互不相同；订单离开 created/paid 后尾差自动释放
+pub struct UniqueAmountAttribution {
+    step: Decimal,
+    max_slots: u32,
+}
+
+impl UniqueAmountAttribution {
+    pub fn new(step: Decimal, max_slots: u32) -> Result<Self> {
+        if step <= Decimal::ZERO || max_slots == 0 {
+            bail!("唯一金额尾差配置无效");
+        }
+        Ok(Self { step, max_slots })
+    }
+}
+
+#[axum::async_trait]
+impl AttributionStrategy for UniqueAmountAttribution {
+    async fn assign(&self, conn: &mut SqliteConnection, order: &PendingOrder<'_>) -> Result<Decimal> {
+        // 独立地址无需尾差
+        if order.derivation_index.is_some() {
+            return Ok(order.amount);
+        }
+        if money::round_for_network(self.step, order.network) != self.step {
+            bail!("尾差精度超出网络支持的精度");
+        }
+
+        // 已占用的尾差金额，条件与唯一索引 idx_order_open_amount 一致
+        let taken: HashSet<Decimal> = sqlx::query!(
+            r#"
+            SELECT amount FROM t_order
+            WHERE network = ?1 AND address = ?2 AND id != ?3
+              AND amount_slot IS NOT NULL AND state IN ('created', 'paid')
+            "#,
+            order.network,
+            order.address,
+            order.id
+        )
+            .fetch_all(&mut *conn)
+            .await
+            .context("查询待支付订单金额失败")?
+            .iter()
+            .filter_map(|r| money::parse(&r.amount).ok())
+            .collect();
+
+        // 由唯一索引兜底：写入冲突时视为已占用，继续尝试下一个尾差
+        for slot in 1..=self.max_slots {
+            let amount = (order.amount + self.step * Decimal::from(slot)).normalize();
+            if taken.contains(&amount) {
+                continue;
+            }
+            let amount_str = amount.to_string();
+            let updated = sqlx::query!(
+                "UPDATE t_order SET amount = ?1, amount_slot = ?2 WHERE id = ?3",
+                amount_str,
+                slot,
+                order.id
+            ).execute(&mut *conn).await;
+            match updated {
+                Ok(_) => return Ok(amount),
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => continue,
+                Err(e) => return Err(e).context("更新订单金额失败"),
+            }
+        }
+        bail!("当前待支付订单过多，请稍后再试")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use sqlx::Connection;
+
+    const ADDRESS: &str = "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH";
+
+    async fn order_db() -> SqliteConnection {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE t_order (id INTEGER PRIMARY KEY, network TEXT NOT NULL, address TEXT NOT NULL,
+                 amount TEXT NOT NULL, state TEXT NOT NULL, amount_slot INTEGER);
+             CREATE UNIQUE INDEX idx_order_open_amount ON t_order (network, address, amount)
+                 WHERE amount_slot IS NOT NULL AND state IN ('created', 'paid');",
+        ).execute(&mut conn).await.unwrap();
+        conn
+    }
+
+    // 插入新订单并按策略确定金额
+    async fn place(conn: &mut SqliteConnection, strategy: &UniqueAmountAttribution, id: i64, derived: bool) -> Result<Decimal> {
+        sqlx::query("INSERT INTO t_order (id, network, address, amount, state) VALUES (?1, 'usdt_trc20', ?2, '3', 'created')")
+            .bind(id)
+            .bind(ADDRESS)
+            .execute(&mut *conn).await.unwrap();
+        let order = PendingOrder {
+            id,
+            network: "usdt_trc20",
+            address: ADDRESS,
+            amount: Decimal::from(3),
+            derivation_index: derived.then_some(id),
+        };
+        strategy.assign(conn, &order).await
+    }
+
+    fn strategy(max_slots: u32) -> UniqueAmountAttribution {
+        UniqueAmountAttribution::new(Decimal::from_str("0.000001").unwrap(), max_slots).unwrap()
+    }
+
+    #[tokio::test]
+    async fn assigns_next_free_slot_and_reuses_released_ones() {
+        let mut conn = order_db().await;
+        let strategy = strategy(3);
+
+        assert_eq!(place(&mut conn, &strategy, 1, false).await.unwrap().to_string(), "3.000001");
+        assert_eq!(place(&mut conn, &strategy, 2, false).await.unwrap().to_string(), "3.000002");
+
+        // 取消后尾差释放
+        sqlx::query("UPDATE t_order SET state = 'cancelled' WHERE id = 1").execute(&mut conn).await.unwrap();
+        assert_eq!(place(&mut conn, &strategy, 3, false).await.unwrap().to_string(), "3.000001");
+
+        let slot: Option<i64> = sqlx::query_scalar("SELECT amount_slot FROM t_order WHERE id = 3")
+            .fetch_one(&mut conn).await.unwrap();
+        assert_eq!(slot, Some(1));
+    }
+
+    #[tokio::test]
+    async fn derived_address_keeps_list_price() {
+        let mut conn = order_db().await;
+        assert_eq!(place(&mut conn, &strategy(3), 1, true).await.unwrap(), Decimal::from(3));
+    }
+
+    #[tokio::test]
+    async fn fails_when_slots_exhausted() {
+        let mut conn = order_db().await;
+        let strategy = strategy(2);
+        place(&mut conn, &strategy, 1, false).await.unwrap();
+        place(&mut conn, &strategy, 2, false).await.unwrap();
+        assert!(place(&mut conn, &strategy, 3, false).await.is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        assert!(UniqueAmountAttribution::new(Decimal::ZERO, 10).is_err());
+        assert!(UniqueAmountAttribution::new(Decimal::ONE, 0).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{bail, Context};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::SqliteConnection;
+
+use crate::models::subscription::PlanType;
+use crate::utils::money;
+
+/// 已占用的优惠码及其折后金额
+pub struct AppliedCoupon {
+    pub coupon_id: i64,
+    pub code: String,
+    pub discount: Decimal,
+    pub amount: Decimal,
+}
+
+/// 在下单事务内占用一次优惠码并计算折后金额。
+/// 先以条件 UPDATE 递增使用次数（同时取得写锁），并发下单不会超出总上限；
+/// 之后的校验失败由调用方回滚事务归还次数。
+pub async fn redeem(
+    conn: &mut SqliteConnection,
+    code: &str,
+    user_id: &str,
+    plan_type: PlanType,
+    network: &str,
+    amount: Decimal,
+) -> anyhow::Result<AppliedCoupon> {
+    let code = code.trim();
+    let now = Utc::now().naive_utc();
+    let coupon = sqlx::query!(
+        r#"
+        UPDATE t_coupon SET redeemed_count = redeemed_count + 1
+        WHERE code = ?1 AND enabled = 1
+          AND (valid_from IS NULL OR valid_from <= ?2)
+          AND (valid_to IS NULL OR valid_to > ?2)
+          AND (max_redemptions IS NULL OR redeemed_count < max_redemptions)
+        RETURNING id as "id!: i64", code, discount_type, discount_value, per_user_limit, plan_types
+        "#,
+        code,
+        now
+    ).fetch_optional(&mut *conn).await.context("查询优惠码失败")?;
+    let Some(coupon) = coupon else {
+        bail!("优惠码无效、已过期或已达使用上限");
+    };
+
+    if let Some(plans) = coupon.plan_types.as_deref() {
+        if !plans.split(',').any(|p| p.trim() == plan_type.as_str()) {
+            bail!("该优惠码不适用于此套餐");
+        }
+    }
+    if let Some(limit) = coupon.per_user_limit {
+        let used = sqlx::query!(
+            r#"SELECT COUNT(1) as "count!: i64" FROM t_coupon_redemption WHERE coupon_id = ?1 AND user_id = ?2"#,
+            coupon.id,
+            user_id
+        ).fetch_one(&mut *conn).await.context("查询优惠码使用记录失败")?;
+        if used.count >= limit {
+            bail!("已达到该优惠码的使用次数上限");
+        }
+    }
+
+    let value = money::parse(&coupon.discount_value)?;
+    let discount = match coupon.discount_type.as_str() {
+        "percent" => amount * value / Decimal::ONE_HUNDRED,
+        "fixed" => value,
+        other => bail!("优惠码类型无效: {}", other),
+    };
+    let discounted = money::round_for_network(amount - discount, network);
+    if discount <= Decimal::ZERO || discounted <= Decimal::ZERO {
+        bail!("该优惠码不适用于此订单金额");
+    }
+    Ok(AppliedCoupon {
+        coupon_id: coupon.id,
+        code: coupon.code,
+        discount: (amount - discounted).normalize(),
+        amount: discounted,
+    })
+}
+
+/// 记录优惠码使用（与订单插入同一事务）
+pub async fn record(
+    conn: &mut SqliteConnection,
+    applied: &AppliedCoupon,
+    user_id: &str,
+    order_id: i64,
+) -> anyhow::Result<()> {
+    let now = Utc::now().naive_utc();
+    let discount = applied.discount.to_string();
+    sqlx::query!(
+        r#"
+        INSERT INTO t_coupon_redemption (coupon_id, user_id, order_id, discount, created)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        applied.coupon_id,
+        user_id,
+        order_id,
+        discount,
+        now
+    ).execute(&mut *conn).await.context("记录优惠码使用失败")?;
+    Ok(())
+}
+
+/// 订单取消或过期时归还优惠码使用次数
+pub async fn release_for_order(conn: &mut SqliteConnection, order_id: i64) -> anyhow::Result<()> {
+    let released = sqlx::query!(
+        r#"DELETE FROM t_coupon_redemption WHERE order_id = ?1 RETURNING coupon_id as "coupon_id!: i64""#,
+        order_id
+    ).fetch_optional(&mut *conn).await.context("释放优惠码失败")?;
+    if let Some(r) = released {
+        sqlx::query!(
+            "UPDATE t_coupon SET redeemed_count = redeemed_count - 1 WHERE id = ?1 AND redeemed_count > 0",
+            r.coupon_id
+        ).execute(&mut *conn).await.context("释放优惠码失败")?;
+    }
+    Ok(())
+}
@@ -2,3 +2,12 @@ pub mod user_service;
 pub mod order_service;
 pub mod article_service;
 pub mod term_service;
+pub mod token_service;
+pub mod subscription_service;
+pub mod payment_service;
+pub mod address_service;
+pub mod plan_service;
+pub mod coupon_service;
+pub mod attribution_service;
+pub mod webhook_service;
+pub mod view_service;
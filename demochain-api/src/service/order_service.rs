@@ -1,18 +1,114 @@
-use crate::models::order::{OrderVO, OrderDTO};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::app::AppSettings;
+use crate::models::order::{AdminOrderQuery, OrderDTO, OrderHistoryVO, OrderState, OrderVO};
 use crate::models::PageVO;
-use crate::utils::jwt_util;
-use anyhow::Context;
+use crate::models::subscription::PlanType;
+use crate::service::address_service::{self, AddressAllocator, ChainFamily};
+use crate::service::attribution_service::{AttributionStrategy, PendingOrder};
+use crate::service::{coupon_service, plan_service, subscription_service, user_service};
+use crate::utils::{jwt_util, money};
+use anyhow::{bail, Context};
 use chrono::{NaiveDateTime, Utc};
-use sqlx::SqlitePool;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+// 状态变更的系统操作方
+pub const ACTOR_SYSTEM: &str = "system";
+pub const ACTOR_PAYMENT: &str = "payment";
+
+// 订单表查询结果（金额为十进制字符串）
+struct OrderRow {
+    id: i64,
+    user_id: String,
+    plan_type: String,
+    amount: String,
+    currency: String,
+    network: String,
+    state: String,
+    address: String,
+    sender_address: Option<String>,
+    tx: Option<String>,
+    coupon_code: Option<String>,
+    discount: Option<String>,
+    expires: Option<NaiveDateTime>,
+    created: NaiveDateTime,
+    updated: NaiveDateTime,
+}
+
+impl OrderRow {
+    fn into_vo(self, history: Vec<OrderHistoryVO>) -> anyhow::Result<OrderVO> {
+        let amount = money::parse(&self.amount)?;
+        let qr_payload = address_service::payment_uri(&self.network, &self.address, amount);
+        Ok(OrderVO {
+            id: self.id.to_string(),
+            user_id: self.user_id,
+            plan_type: self.plan_type,
+            amount,
+            currency: self.currency,
+            network: self.network,
+            state: self.state,
+            address: self.address,
+            sender_address: self.sender_address,
+            tx: self.tx,
+            coupon_code: self.coupon_code,
+            discount: self.discount.as_deref().map(money::parse).transpose()?,
+            expires: self.expires.map(|t| t.and_utc()),
+            qr_payload,
+            created: self.created.and_utc(),
+            updated: self.updated.and_utc(),
+            history,
+        })
+    }
+}
 
-fn price_for_plan(plan: &str) -> Option<f64> {
-    match plan {
-        "monthly" => Some(3.0),
-        "yearly" => Some(10.0),
-        "lifetime" => Some(15.0),
-        _ => None,
+/// 批量加载订单状态变更记录
+async fn load_history(pool: &SqlitePool, order_ids: &[i64]) -> anyhow::Result<HashMap<i64, Vec<OrderHistoryVO>>> {
+    let ids = serde_json::to_string(order_ids)?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            order_id,
+            from_state,
+            to_state,
+            actor,
+            reason,
+            created as "created: NaiveDateTime"
+        FROM t_order_history
+        WHERE order_id IN (SELECT value FROM json_each(?1))
+        ORDER BY id ASC
+        "#,
+        ids
+    )
+        .fetch_all(pool)
+        .await
+        .with_context(|| "查询订单状态记录失败")?;
+    let mut history: HashMap<i64, Vec<OrderHistoryVO>> = HashMap::new();
+    for h in rows {
+        history.entry(h.order_id).or_default().push(OrderHistoryVO {
+            from_state: h.from_state,
+            to_state: h.to_state,
+            actor: h.actor,
+            reason: h.reason,
+            created: h.created.and_utc(),
+        });
     }
+    Ok(history)
+}
+
+async fn with_history(pool: &SqlitePool, rows: Vec<OrderRow>) -> anyhow::Result<Vec<OrderVO>> {
+    let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+    let mut history = load_history(pool, &ids).await?;
+    rows.into_iter()
+        .map(|r| {
+            let h = history.remove(&r.id).unwrap_or_default();
+            r.into_vo(h)
+        })
+        .collect()
 }
+
 pub async fn page(
     pool: &SqlitePool,
     page: i64,
@@ -36,7 +132,8 @@ pub async fn page(
         .await
         .with_context(|| "查询订单总数失败")?;
 
-    let rows = sqlx::query!(
+    let rows = sqlx::query_as!(
+        OrderRow,
         r#"
         SELECT 
             id as "id!: i64",
@@ -49,6 +146,9 @@ pub async fn page(
             address,
             sender_address,
             tx,
+            coupon_code,
+            discount,
+            expires as "expires: NaiveDateTime",
             created as "created: NaiveDateTime",
             updated as "updated: NaiveDateTime"
         FROM t_order
@@ -64,43 +164,161 @@ pub async fn page(
         .await
         .with_context(|| "查询订单失败")?;
 
-    let mut out = Vec::with_capacity(rows.len());
+    let items = with_history(pool, rows).await?;
+    Ok(PageVO { items, total: total_row.count, page, size: limit })
+}
+
+/// 管理端：跨用户分页查询订单，q 匹配订单号、交易哈希、收款/付款地址或用户邮箱
+pub async fn search(pool: &SqlitePool, query: AdminOrderQuery) -> anyhow::Result<PageVO<OrderVO>> {
+    let limit = query.size.filter(|s| *s > 0).unwrap_or(20);
+    let page = query.page.filter(|p| *p > 0).unwrap_or(1);
+    let offset = (page - 1) * limit;
+    let q = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let pattern = q.map(|q| format!("%{}%", q));
+
+    let total_row = sqlx::query!(
+        r#"
+        SELECT COUNT(1) as "count!: i64" FROM t_order o
+        WHERE (?1 IS NULL OR o.user_id = ?1)
+          AND (?2 IS NULL OR o.state = ?2)
+          AND (?3 IS NULL OR o.network = ?3)
+          AND (?4 IS NULL OR CAST(o.id AS TEXT) = ?4 OR o.tx LIKE ?5 OR o.address LIKE ?5
+               OR o.sender_address LIKE ?5
+               OR o.user_id IN (SELECT CAST(id AS TEXT) FROM t_user WHERE email LIKE ?5))
+        "#,
+        query.user_id,
+        query.state,
+        query.network,
+        q,
+        pattern
+    )
+        .fetch_one(pool)
+        .await
+        .with_context(|| "查询订单总数失败")?;
+
+    let rows = sqlx::query_as!(
+        OrderRow,
+        r#"
+        SELECT
+            o.id as "id!: i64",
+            o.user_id,
+            o.plan_type,
+            o.amount,
+            o.currency,
+            o.network,
+            o.state,
+            o.address,
+            o.sender_address,
+            o.tx,
+            o.coupon_code,
+            o.discount,
+            o.expires as "expires: NaiveDateTime",
+            o.created as "created: NaiveDateTime",
+            o.updated as "updated: NaiveDateTime"
+        FROM t_order o
+        WHERE (?1 IS NULL OR o.user_id = ?1)
+          AND (?2 IS NULL OR o.state = ?2)
+          AND (?3 IS NULL OR o.network = ?3)
+          AND (?4 IS NULL OR CAST(o.id AS TEXT) = ?4 OR o.tx LIKE ?5 OR o.address LIKE ?5
+               OR o.sender_address LIKE ?5
+               OR o.user_id IN (SELECT CAST(id AS TEXT) FROM t_user WHERE email LIKE ?5))
+        ORDER BY o.created DESC
+        LIMIT ?6 OFFSET ?7
+        "#,
+        query.user_id,
+        query.state,
+        query.network,
+        q,
+        pattern,
+        limit,
+        offset
+    )
+        .fetch_all(pool)
+        .await
+        .with_context(|| "查询订单失败")?;
+
+    let items = with_history(pool, rows).await?;
+    Ok(PageVO { items, total: total_row.count, page, size: limit })
+}
+
+/// 导出当前用户的全部订单（CSV）
+pub async fn export_csv(pool: &SqlitePool, user_id: &str) -> anyhow::Result<String> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id as "id!: i64",
+            plan_type,
+            amount,
+            currency,
+            network,
+            state,
+            address,
+            tx,
+            created as "created: NaiveDateTime"
+        FROM t_order
+        WHERE user_id = ?1
+        ORDER BY created DESC
+        "#,
+        user_id
+    )
+        .fetch_all(pool)
+        .await
+        .with_context(|| "查询订单失败")?;
+
+    let mut csv = String::from("id,plan_type,amount,currency,network,state,address,tx,created\n");
     for r in rows {
-        let order = OrderVO {
-            id: r.id.to_string(),
-            user_id: r.user_id,
-            plan_type: r.plan_type,
-            amount: r.amount,
-            currency: r.currency,
-            network: r.network,
-            state: r.state,
-            address: r.address,
-            sender_address: r.sender_address,
-            tx: r.tx,
-            created: r.created.and_utc(),
-            updated: r.updated.and_utc(),
-        };
-        out.push(order);
-    }
-
-    Ok(PageVO { items: out, total: total_row.count, page, size: limit })
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            r.id,
+            r.plan_type,
+            r.amount,
+            r.currency,
+            r.network,
+            r.state,
+            r.address,
+            r.tx.unwrap_or_default(),
+            r.created.and_utc().to_rfc3339()
+        ));
+    }
+    Ok(csv)
 }
 
 pub async fn add(
     pool: &SqlitePool,
+    addresses: &AddressAllocator,
+    attribution: &dyn AttributionStrategy,
+    settings: &AppSettings,
     payload: OrderDTO,
-) -> anyhow::Result<String> {
-    // 1. 计算订单金额
-    let amount = price_for_plan(&payload.plan_type).context("不支持的套餐")?;
-    // 2. 固定收款地址（你可以改成动态分配逻辑）
-    let address = "0x909b17701d00c156b630C92497fdc1f1ae39fED4";
-    // 3. 获取当前登录用户
+) -> anyhow::Result<OrderVO> {
+    // 1. 校验套餐与网络，按价格目录计算订单金额（下单时定价，之后调价不影响已有订单）
+    let plan_type = PlanType::parse(&payload.plan_type).context("不支持的套餐")?;
+    if ChainFamily::of(&payload.network).is_none() {
+        bail!("不支持的网络: {}", payload.network);
+    }
+    let price = plan_service::current_price(pool, plan_type, &payload.network)
+        .await?
+        .context("该套餐暂不支持此网络")?;
+    // 2. 获取当前登录用户
     let user_id = jwt_util::get_user_id().ok_or_else(|| anyhow::anyhow!("用户未登录"))?;
-    // 4. 生成时间（使用 NaiveDateTime，便于与 SQLite datetime 字段匹配）
+    if settings.require_verified_email {
+        user_service::ensure_email_verified(pool, &user_id).await?;
+    }
+    // 3. 生成时间（使用 NaiveDateTime，便于与 SQLite datetime 字段匹配）
     let now = Utc::now().naive_utc();
+    let expires = now + settings.order_expiry;
 
-    // 5. 插入订单记录
-    sqlx::query!(
+    // 4. 占用优惠码、分配收款地址并插入订单记录（同一事务，失败时优惠码次数与派生索引一并回滚）
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    let coupon = match payload.coupon.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        Some(code) => Some(coupon_service::redeem(&mut tx, code, &user_id, plan_type, &payload.network, price.amount).await?),
+        None => None,
+    };
+    let amount = coupon.as_ref().map_or(price.amount, |c| c.amount);
+    let coupon_code = coupon.as_ref().map(|c| c.code.clone());
+    let discount = coupon.as_ref().map(|c| c.discount);
+    let (amount_str, discount_str) = (amount.to_string(), discount.map(|d| d.to_string()));
+    let allocated = addresses.allocate(&mut tx, &payload.network).await?;
+    let inserted = sqlx::query!(
         r#"
         INSERT INTO t_order (
             user_id,
@@ -112,21 +330,506 @@ pub async fn add(
             address,
             sender_address,
             tx,
+            derivation_index,
+            coupon_code,
+            discount,
+            expires,
             created,
             updated
         )
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, NULL, ?8, ?9)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, NULL, ?8, ?9, ?10, ?11, ?12, ?13)
         "#,
         user_id,
         payload.plan_type,
-        amount,
-        "USDT",
+        amount_str,
+        price.currency,
         payload.network,
         "created",
-        address,
+        allocated.address,
+        allocated.derivation_index,
+        coupon_code,
+        discount_str,
+        expires,
         now,
         now
-    ).execute(pool).await.context("插入订单失败")?;
-    // 6. 返回收款地址（与 handler 的 Response<String> 对齐）
-    Ok(address.to_string())
-}
\ No newline at end of file
+    ).execute(&mut *tx).await.context("插入订单失败")?;
+    let order_id = inserted.last_insert_rowid();
+    // 5. 按归属策略确定最终应付金额（可能附加唯一尾差）
+    let amount = attribution.assign(&mut tx, &PendingOrder {
+        id: order_id,
+        network: &payload.network,
+        address: &allocated.address,
+        amount,
+        derivation_index: allocated.derivation_index,
+    }).await?;
+    if let Some(applied) = &coupon {
+        coupon_service::record(&mut tx, applied, &user_id, order_id).await?;
+    }
+    tx.commit().await.context("提交事务失败")?;
+    // 6. 返回订单详情（含收款地址与二维码内容）
+    let qr_payload = address_service::payment_uri(&payload.network, &allocated.address, amount);
+    Ok(OrderVO {
+        id: order_id.to_string(),
+        user_id,
+        plan_type: payload.plan_type,
+        amount,
+        currency: price.currency,
+        network: payload.network,
+        state: OrderState::Created.as_str().to_string(),
+        address: allocated.address,
+        sender_address: None,
+        tx: None,
+        coupon_code,
+        discount,
+        expires: Some(expires.and_utc()),
+        qr_payload,
+        created: now.and_utc(),
+        updated: now.and_utc(),
+        history: vec![],
+    })
+}
+
+/// 查询当前用户的单个订单
+pub async fn get(pool: &SqlitePool, user_id: &str, order_id: i64) -> anyhow::Result<Option<OrderVO>> {
+    let row = sqlx::query_as!(
+        OrderRow,
+        r#"
+        SELECT
+            id as "id!: i64",
+            user_id,
+            plan_type,
+            amount,
+            currency,
+            network,
+            state,
+            address,
+            sender_address,
+            tx,
+            coupon_code,
+            discount,
+            expires as "expires: NaiveDateTime",
+            created as "created: NaiveDateTime",
+            updated as "updated: NaiveDateTime"
+        FROM t_order
+        WHERE id = ?1 AND user_id = ?2
+        "#,
+        order_id,
+        user_id
+    )
+        .fetch_optional(pool)
+        .await
+        .with_context(|| "查询订单失败")?;
+    match row {
+        Some(r) => Ok(with_history(pool, vec![r]).await?.pop()),
+        None => Ok(None),
+    }
+}
+
+// 长轮询的最长等待时间；状态变更由进程内通知唤醒，慢速轮询仅作兜底（如其他进程直接改库）
+const MAX_WAIT_SECONDS: u64 = 30;
+const WAIT_FALLBACK_POLL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// 按订单ID登记的长轮询等待者
+fn order_watchers() -> &'static Mutex<HashMap<i64, Arc<Notify>>> {
+    static WATCHERS: OnceLock<Mutex<HashMap<i64, Arc<Notify>>>> = OnceLock::new();
+    WATCHERS.get_or_init(Default::default)
+}
+
+/// 长轮询对单个订单的登记，离开时若无其他等待者则移除
+struct OrderWatch {
+    order_id: i64,
+    notify: Arc<Notify>,
+}
+
+impl OrderWatch {
+    fn new(order_id: i64) -> Self {
+        let notify = order_watchers().lock().unwrap().entry(order_id).or_default().clone();
+        Self { order_id, notify }
+    }
+}
+
+impl Drop for OrderWatch {
+    fn drop(&mut self) {
+        let mut watchers = order_watchers().lock().unwrap();
+        // 仅剩登记表与自身持有时移除
+        if Arc::strong_count(&self.notify) == 2 {
+            watchers.remove(&self.order_id);
+        }
+    }
+}
+
+/// 唤醒等待该订单状态变化的长轮询
+fn notify_order_changed(order_id: i64) {
+    if let Some(notify) = order_watchers().lock().unwrap().get(&order_id) {
+        notify.notify_waiters();
+    }
+}
+
+/// 提交状态迁移事务并唤醒长轮询；须在提交后通知，否则等待者会读到旧状态
+async fn commit_transition(db_tx: Transaction<'_, Sqlite>, order_id: i64) -> anyhow::Result<()> {
+    db_tx.commit().await.context("提交事务失败")?;
+    notify_order_changed(order_id);
+    Ok(())
+}
+
+/// 长轮询：等待订单状态不再等于 known_state，或等待超时后返回最新订单
+pub async fn wait_for_change(
+    pool: &SqlitePool,
+    user_id: &str,
+    order_id: i64,
+    known_state: &str,
+    wait_seconds: u64,
+) -> anyhow::Result<Option<OrderVO>> {
+    let deadline = tokio::time::Instant::now()
+        + std::time::Duration::from_secs(wait_seconds.min(MAX_WAIT_SECONDS));
+    let watch = OrderWatch::new(order_id);
+    loop {
+        // 先登记通知再查库，避免错过查询与等待之间发生的变更
+        let notified = watch.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        let order = get(pool, user_id, order_id).await?;
+        let changed = order.as_ref().is_none_or(|o| o.state != known_state);
+        let now = tokio::time::Instant::now();
+        if changed || now >= deadline {
+            return Ok(order);
+        }
+        let _ = tokio::time::timeout_at(deadline.min(now + WAIT_FALLBACK_POLL), notified).await;
+    }
+}
+
+/// 链上交易是否已被某个订单占用
+pub async fn tx_claimed(pool: &SqlitePool, tx: &str) -> anyhow::Result<bool> {
+    let row = sqlx::query!("SELECT id FROM t_order WHERE tx = ?1", tx)
+        .fetch_optional(pool)
+        .await
+        .context("查询订单交易失败")?;
+    Ok(row.is_some())
+}
+
+/// 按状态机迁移订单状态并记录审计，返回迁移前的状态；
+/// 调用方通过 commit_transition 提交以唤醒该订单的长轮询
+pub async fn transition(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+    to: OrderState,
+    actor: &str,
+    reason: Option<&str>,
+) -> anyhow::Result<OrderState> {
+    let now = Utc::now().naive_utc();
+    // 先写入以取得写锁，避免事务内先读后写时 SQLite 锁升级失败（database is locked）
+    let touched = sqlx::query!("UPDATE t_order SET updated = ?1 WHERE id = ?2", now, order_id)
+        .execute(&mut *conn)
+        .await
+        .context("更新订单失败")?;
+    if touched.rows_affected() != 1 {
+        bail!("订单不存在");
+    }
+    let row = sqlx::query!("SELECT state FROM t_order WHERE id = ?1", order_id)
+        .fetch_one(&mut *conn)
+        .await
+        .context("查询订单失败")?;
+    let from = OrderState::parse(&row.state).context("订单状态无效")?;
+    if !from.can_transition_to(to) {
+        bail!("订单状态不允许从 {} 变更为 {}", from.as_str(), to.as_str());
+    }
+
+    let (from_str, to_str) = (from.as_str(), to.as_str());
+    sqlx::query!("UPDATE t_order SET state = ?1 WHERE id = ?2", to_str, order_id)
+        .execute(&mut *conn)
+        .await
+        .context("更新订单状态失败")?;
+    sqlx::query!(
+        r#"
+        INSERT INTO t_order_history (order_id, from_state, to_state, actor, reason, created)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        order_id,
+        from_str,
+        to_str,
+        actor,
+        reason,
+        now
+    ).execute(&mut *conn).await.context("写入订单状态记录失败")?;
+    if matches!(to, OrderState::Cancelled | OrderState::Expired) {
+        coupon_service::release_for_order(conn, order_id).await?;
+    }
+    Ok(from)
+}
+
+/// 记录付款方与交易哈希（仅在尚未记录时写入）
+async fn record_payment(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+    sender_address: Option<&str>,
+    tx: Option<&str>,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE t_order
+        SET sender_address = COALESCE(?1, sender_address), tx = COALESCE(?2, tx)
+        WHERE id = ?3
+        "#,
+        sender_address,
+        tx,
+        order_id
+    ).execute(&mut *conn).await.context("更新订单付款信息失败")?;
+    Ok(())
+}
+
+/// 检测到付款但确认数不足：created -> paid
+pub async fn mark_paid(
+    pool: &SqlitePool,
+    order_id: i64,
+    sender_address: &str,
+    tx: &str,
+) -> anyhow::Result<()> {
+    let mut db_tx = pool.begin().await.context("开启事务失败")?;
+    transition(&mut db_tx, order_id, OrderState::Paid, ACTOR_PAYMENT, None).await?;
+    record_payment(&mut db_tx, order_id, Some(sender_address), Some(tx)).await?;
+    commit_transition(db_tx, order_id).await?;
+    Ok(())
+}
+
+/// 确认订单并在同一事务内发放对应权益
+pub async fn confirm(
+    pool: &SqlitePool,
+    order_id: i64,
+    actor: &str,
+    reason: Option<&str>,
+    sender_address: Option<&str>,
+    tx: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut db_tx = pool.begin().await.context("开启事务失败")?;
+    transition(&mut db_tx, order_id, OrderState::Confirmed, actor, reason).await?;
+    record_payment(&mut db_tx, order_id, sender_address, tx).await?;
+    subscription_service::grant_for_order(&mut db_tx, order_id).await?;
+    commit_transition(db_tx, order_id).await?;
+    Ok(())
+}
+
+/// 退款并在同一事务内撤销对应权益
+pub async fn refund(pool: &SqlitePool, order_id: i64, actor: &str, reason: &str) -> anyhow::Result<()> {
+    let mut db_tx = pool.begin().await.context("开启事务失败")?;
+    transition(&mut db_tx, order_id, OrderState::Refunded, actor, Some(reason)).await?;
+    subscription_service::revoke_for_order(&mut db_tx, order_id).await?;
+    commit_transition(db_tx, order_id).await?;
+    Ok(())
+}
+
+/// 用户取消自己尚未支付的订单
+pub async fn cancel(
+    pool: &SqlitePool,
+    user_id: &str,
+    order_id: i64,
+    reason: Option<&str>,
+) -> anyhow::Result<()> {
+    let owned = sqlx::query!(
+        "SELECT id FROM t_order WHERE id = ?1 AND user_id = ?2",
+        order_id,
+        user_id
+    ).fetch_optional(pool).await.context("查询订单失败")?;
+    if owned.is_none() {
+        bail!("订单不存在");
+    }
+    let mut db_tx = pool.begin().await.context("开启事务失败")?;
+    transition(&mut db_tx, order_id, OrderState::Cancelled, user_id, reason).await?;
+    commit_transition(db_tx, order_id).await?;
+    Ok(())
+}
+
+/// 将超时未支付的订单置为 expired，返回处理数量
+pub async fn expire_overdue(pool: &SqlitePool) -> anyhow::Result<u64> {
+    let now = Utc::now().naive_utc();
+    let overdue = sqlx::query!(
+        r#"SELECT id as "id!: i64" FROM t_order WHERE state = 'created' AND expires < ?1"#,
+        now
+    ).fetch_all(pool).await.context("查询超时订单失败")?;
+
+    let mut expired = 0;
+    for order in overdue {
+        let mut db_tx = pool.begin().await.context("开启事务失败")?;
+        // 期间可能已被支付或取消，迁移失败时跳过
+        match transition(&mut db_tx, order.id, OrderState::Expired, ACTOR_SYSTEM, Some("超时未支付")).await {
+            Ok(_) => {
+                commit_transition(db_tx, order.id).await?;
+                expired += 1;
+            }
+            Err(e) => warn!(order_id = order.id, error = %e, "skip expiring order"),
+        }
+    }
+    Ok(expired)
+}
+
+/// 启动后台任务：定期过期超时未支付的订单
+pub fn spawn_expiry_sweeper(pool: SqlitePool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            match expire_overdue(&pool).await {
+                Ok(0) => {}
+                Ok(n) => info!(orders = n, "expired overdue orders"),
+                Err(e) => error!(error = %e, "failed to expire overdue orders"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+    use OrderState::*;
+
+    const ALL: [OrderState; 6] = [Created, Paid, Confirmed, Expired, Cancelled, Refunded];
+
+    // 仅包含 transition 涉及的表与列
+    async fn order_db() -> SqliteConnection {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE t_order (id INTEGER PRIMARY KEY, state TEXT NOT NULL, updated DATETIME NOT NULL DEFAULT (datetime('now')));
+             CREATE TABLE t_order_history (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT, order_id INTEGER NOT NULL, from_state TEXT NOT NULL,
+                 to_state TEXT NOT NULL, actor TEXT NOT NULL, reason TEXT, created DATETIME NOT NULL);
+             CREATE TABLE t_coupon (id INTEGER PRIMARY KEY, redeemed_count INTEGER NOT NULL DEFAULT 0);
+             CREATE TABLE t_coupon_redemption (id INTEGER PRIMARY KEY AUTOINCREMENT, coupon_id INTEGER NOT NULL, order_id INTEGER NOT NULL UNIQUE);",
+        ).execute(&mut conn).await.unwrap();
+        conn
+    }
+
+    async fn insert_order(conn: &mut SqliteConnection, id: i64, state: OrderState) {
+        sqlx::query("INSERT INTO t_order (id, state) VALUES (?1, ?2)")
+            .bind(id)
+            .bind(state.as_str())
+            .execute(&mut *conn).await.unwrap();
+    }
+
+    async fn state_of(conn: &mut SqliteConnection, id: i64) -> String {
+        sqlx::query_scalar("SELECT state FROM t_order WHERE id = ?1")
+            .bind(id)
+            .fetch_one(&mut *conn).await.unwrap()
+    }
+
+    async fn history_of(conn: &mut SqliteConnection, id: i64) -> Vec<(String, String, String, Option<String>)> {
+        sqlx::query_as("SELECT from_state, to_state, actor, reason FROM t_order_history WHERE order_id = ?1 ORDER BY id")
+            .bind(id)
+            .fetch_all(&mut *conn).await.unwrap()
+    }
+
+    #[test]
+    fn transition_table() {
+        let allowed = [
+            (Created, Paid),
+            (Created, Confirmed),
+            (Created, Expired),
+            (Created, Cancelled),
+            (Paid, Confirmed),
+            (Paid, Refunded),
+            (Confirmed, Refunded),
+        ];
+        for from in ALL {
+            for to in ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{} -> {}", from.as_str(), to.as_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn terminal_states_have_no_exit() {
+        for from in [Expired, Cancelled, Refunded] {
+            assert!(ALL.iter().all(|to| !from.can_transition_to(*to)), "{}", from.as_str());
+        }
+    }
+
+    #[test]
+    fn state_names_round_trip() {
+        for s in ALL {
+            assert_eq!(OrderState::parse(s.as_str()), Some(s));
+        }
+        assert_eq!(OrderState::parse("pending"), None);
+    }
+
+    #[tokio::test]
+    async fn transition_updates_state_and_writes_history() {
+        let mut conn = order_db().await;
+        insert_order(&mut conn, 1, Created).await;
+
+        let from = transition(&mut conn, 1, Paid, ACTOR_PAYMENT, None).await.unwrap();
+        assert_eq!(from, Created);
+        let from = transition(&mut conn, 1, Confirmed, "admin-1", Some("人工确认")).await.unwrap();
+        assert_eq!(from, Paid);
+
+        assert_eq!(state_of(&mut conn, 1).await, "confirmed");
+        assert_eq!(history_of(&mut conn, 1).await, vec![
+            ("created".into(), "paid".into(), ACTOR_PAYMENT.into(), None),
+            ("paid".into(), "confirmed".into(), "admin-1".into(), Some("人工确认".into())),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn forbidden_transition_leaves_order_untouched() {
+        let mut conn = order_db().await;
+        insert_order(&mut conn, 1, Paid).await;
+        insert_order(&mut conn, 2, Expired).await;
+
+        assert!(transition(&mut conn, 1, Cancelled, "user-1", None).await.is_err());
+        assert!(transition(&mut conn, 1, Expired, ACTOR_SYSTEM, None).await.is_err());
+        assert!(transition(&mut conn, 2, Paid, ACTOR_PAYMENT, None).await.is_err());
+        assert!(transition(&mut conn, 3, Paid, ACTOR_PAYMENT, None).await.is_err());
+
+        assert_eq!(state_of(&mut conn, 1).await, "paid");
+        assert_eq!(state_of(&mut conn, 2).await, "expired");
+        assert!(history_of(&mut conn, 1).await.is_empty());
+        assert!(history_of(&mut conn, 2).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_and_expire_release_coupon() {
+        let mut conn = order_db().await;
+        sqlx::query(
+            "INSERT INTO t_coupon (id, redeemed_count) VALUES (1, 3);
+             INSERT INTO t_coupon_redemption (coupon_id, order_id) VALUES (1, 1), (1, 2), (1, 3);",
+        ).execute(&mut conn).await.unwrap();
+        insert_order(&mut conn, 1, Created).await;
+        insert_order(&mut conn, 2, Created).await;
+        insert_order(&mut conn, 3, Created).await;
+
+        transition(&mut conn, 1, Cancelled, "user-1", None).await.unwrap();
+        transition(&mut conn, 2, Expired, ACTOR_SYSTEM, Some("超时未支付")).await.unwrap();
+        // 确认不归还优惠码
+        transition(&mut conn, 3, Confirmed, ACTOR_PAYMENT, None).await.unwrap();
+
+        let redeemed: i64 = sqlx::query_scalar("SELECT redeemed_count FROM t_coupon WHERE id = 1")
+            .fetch_one(&mut conn).await.unwrap();
+        assert_eq!(redeemed, 1);
+        let remaining: Vec<i64> = sqlx::query_scalar("SELECT order_id FROM t_coupon_redemption ORDER BY order_id")
+            .fetch_all(&mut conn).await.unwrap();
+        assert_eq!(remaining, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn order_change_wakes_watcher_and_unregisters() {
+        let watch = OrderWatch::new(42);
+        {
+            let notified = watch.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            notify_order_changed(41);
+            notify_order_changed(42);
+            tokio::time::timeout(std::time::Duration::from_secs(1), notified).await.unwrap();
+        }
+
+        let second = OrderWatch::new(42);
+        drop(watch);
+        assert!(order_watchers().lock().unwrap().contains_key(&42));
+        drop(second);
+        assert!(!order_watchers().lock().unwrap().contains_key(&42));
+    }
+}
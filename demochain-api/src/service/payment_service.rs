@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::SqlitePool;
+use tracing::{error, info, warn};
+
+use crate::models::order::{UnmatchedTransferQuery, UnmatchedTransferVO};
+use crate::models::PageVO;
+use crate::service::order_service;
+use crate::utils::chain_indexer::{ChainIndexer, TokenTransfer};
+use crate::utils::money;
+
+// 过期/取消后继续观察收款地址的时长，期间到账的转账记为未匹配
+const LATE_TRANSFER_WATCH_DAYS: i64 = 7;
+
+// 未匹配转账的发现来源
+pub const SOURCE_WATCHER: &str = "watcher";
+pub const SOURCE_WEBHOOK: &str = "webhook";
+
+/// 支付对账配置
+#[derive(Clone)]
+pub struct PaymentConfig {
+    pub poll_interval: Duration,
+    // 统一覆盖各网络的确认数，None 时使用网络默认值
+    pub confirmations: Option<u64>,
+}
+
+impl PaymentConfig {
+    fn required_confirmations(&self, network: &str) -> u64 {
+        self.confirmations.unwrap_or(match network {
+            "usdt_trc20" => 19,
+            "usdt_bep20" => 15,
+            _ => 12,
+        })
+    }
+}
+
+/// 启动后台对账任务：轮询链上索引，把订单从 created 推进到 paid / confirmed
+pub fn spawn_watcher(pool: SqlitePool, indexer: Arc<dyn ChainIndexer>, cfg: PaymentConfig) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(cfg.poll_interval);
+        loop {
+            ticker.tick().await;
+            match reconcile_once(&pool, indexer.as_ref(), &cfg).await {
+                Ok(0) => {}
+                Ok(n) => info!(orders = n, "payment reconciliation progressed orders"),
+                Err(e) => error!(error = %e, "payment reconciliation failed"),
+            }
+        }
+    });
+}
+
+// 待支付订单（created / paid）
+struct OpenOrder {
+    id: i64,
+    amount: Decimal,
+    network: String,
+    state: String,
+    address: String,
+    tx: Option<String>,
+    created: NaiveDateTime,
+}
+
+/// 查询待支付订单，可按网络与收款地址过滤（地址不区分大小写）
+async fn open_orders(pool: &SqlitePool, target: Option<(&str, &str)>) -> Result<Vec<OpenOrder>> {
+    let (network, address) = target.unzip();
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id as "id!: i64",
+            amount,
+            network,
+            state,
+            address,
+            tx,
+            created as "created: NaiveDateTime"
+        FROM t_order
+        WHERE state IN ('created', 'paid')
+          AND (?1 IS NULL OR network = ?1)
+          AND (?2 IS NULL OR lower(address) = lower(?2))
+        ORDER BY created ASC
+        "#,
+        network,
+        address
+    ).fetch_all(pool).await.context("查询待支付订单失败")?;
+
+    let mut orders = Vec::with_capacity(rows.len());
+    for r in rows {
+        let amount = match money::parse(&r.amount) {
+            Ok(amount) => amount,
+            Err(e) => {
+                warn!(order_id = r.id, error = %e, "invalid order amount");
+                continue;
+            }
+        };
+        orders.push(OpenOrder {
+            id: r.id,
+            amount,
+            network: r.network,
+            state: r.state,
+            address: r.address,
+            tx: r.tx,
+            created: r.created,
+        });
+    }
+    Ok(orders)
+}
+
+/// 在候选转账中找到属于该订单的一笔
+async fn find_match<'a>(
+    pool: &SqlitePool,
+    order: &OpenOrder,
+    transfers: &'a [TokenTransfer],
+) -> Result<Option<&'a TokenTransfer>> {
+    // 已匹配过交易（paid），只等待确认数
+    if let Some(tx) = &order.tx {
+        return Ok(transfers.iter().find(|t| &t.tx == tx));
+    }
+    for t in transfers {
+        if t.timestamp.naive_utc() >= order.created
+            && t.amount == order.amount
+            && !order_service::tx_claimed(pool, &t.tx).await?
+        {
+            return Ok(Some(t));
+        }
+    }
+    Ok(None)
+}
+
+/// 按确认数推进订单状态，返回是否有推进
+async fn progress(pool: &SqlitePool, cfg: &PaymentConfig, order: &OpenOrder, transfer: &TokenTransfer) -> Result<bool> {
+    if transfer.confirmations >= cfg.required_confirmations(&order.network) {
+        order_service::confirm(pool, order.id, order_service::ACTOR_PAYMENT, None, Some(&transfer.from), Some(&transfer.tx)).await?;
+    } else if order.state == "created" {
+        order_service::mark_paid(pool, order.id, &transfer.from, &transfer.tx).await?;
+    } else {
+        return Ok(false);
+    }
+    info!(order_id = order.id, tx = %transfer.tx, confirmations = transfer.confirmations, "order payment progressed");
+    Ok(true)
+}
+
+/// 处理一笔推送来的转账（如 webhook），返回被推进的订单；已确认但无订单可匹配的转账记为未匹配
+pub async fn apply_transfer(pool: &SqlitePool, cfg: &PaymentConfig, transfer: &TokenTransfer) -> Result<Option<i64>> {
+    let mut orders = open_orders(pool, Some((&transfer.network, &transfer.to))).await?;
+    // 已记录该交易的订单优先，其余按下单先后匹配
+    orders.sort_by_key(|o| o.tx.as_deref() != Some(transfer.tx.as_str()));
+    for order in &orders {
+        if find_match(pool, order, std::slice::from_ref(transfer)).await?.is_some() {
+            return Ok(progress(pool, cfg, order, transfer).await?.then_some(order.id));
+        }
+    }
+    record_if_unmatched(pool, cfg, transfer, SOURCE_WEBHOOK).await?;
+    Ok(None)
+}
+
+/// 已达到确认数、且没有被任何订单认领的转账记入未匹配表（同一交易只记一次）
+async fn record_if_unmatched(pool: &SqlitePool, cfg: &PaymentConfig, transfer: &TokenTransfer, source: &str) -> Result<()> {
+    if transfer.confirmations < cfg.required_confirmations(&transfer.network)
+        || order_service::tx_claimed(pool, &transfer.tx).await?
+    {
+        return Ok(());
+    }
+    let now = Utc::now().naive_utc();
+    let amount = transfer.amount.to_string();
+    let block_time = transfer.timestamp.naive_utc();
+    let inserted = sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO t_unmatched_transfer
+            (network, tx, from_address, to_address, amount, block_time, order_id, source, status, created)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6,
+            (SELECT id FROM t_order WHERE network = ?1 AND lower(address) = lower(?4) ORDER BY created DESC LIMIT 1),
+            ?7, 'open', ?8)
+        "#,
+        transfer.network,
+        transfer.tx,
+        transfer.from,
+        transfer.to,
+        amount,
+        block_time,
+        source,
+        now
+    ).execute(pool).await.context("记录未匹配转账失败")?;
+    if inserted.rows_affected() > 0 {
+        warn!(network = %transfer.network, tx = %transfer.tx, amount = %transfer.amount, source, "unmatched transfer recorded");
+    }
+    Ok(())
+}
+
+/// 最近过期或取消的订单的收款地址（这些地址已没有待支付订单，但仍可能收到迟到的转账）
+async fn recently_closed_addresses(pool: &SqlitePool) -> Result<Vec<(String, String)>> {
+    let since = Utc::now().naive_utc() - chrono::Duration::days(LATE_TRANSFER_WATCH_DAYS);
+    let rows = sqlx::query!(
+        r#"
+        SELECT DISTINCT network, address FROM t_order
+        WHERE state IN ('expired', 'cancelled') AND updated >= ?1
+        "#,
+        since
+    ).fetch_all(pool).await.context("查询已关闭订单失败")?;
+    Ok(rows.into_iter().map(|r| (r.network, r.address)).collect())
+}
+
+// 同一网络同一地址只查询一次索引；查询失败返回 false
+async fn fetch_transfers(
+    indexer: &dyn ChainIndexer,
+    cache: &mut HashMap<(String, String), Vec<TokenTransfer>>,
+    key: &(String, String),
+) -> bool {
+    if cache.contains_key(key) {
+        return true;
+    }
+    match indexer.transfers_to(&key.0, &key.1).await {
+        Ok(transfers) => {
+            cache.insert(key.clone(), transfers);
+            true
+        }
+        Err(e) => {
+            warn!(network = %key.0, address = %key.1, error = %e, "indexer query failed");
+            false
+        }
+    }
+}
+
+/// 执行一轮对账，返回状态有推进的订单数；本轮观察到但未被任何订单认领的已确认转账记为未匹配
+pub async fn reconcile_once(pool: &SqlitePool, indexer: &dyn ChainIndexer, cfg: &PaymentConfig) -> Result<usize> {
+    let orders = open_orders(pool, None).await?;
+
+    let mut transfers_cache: HashMap<(String, String), Vec<TokenTransfer>> = HashMap::new();
+    let mut progressed = 0;
+    for order in orders {
+        let key = (order.network.clone(), order.address.clone());
+        if !fetch_transfers(indexer, &mut transfers_cache, &key).await {
+            continue;
+        }
+        let Some(transfer) = find_match(pool, &order, &transfers_cache[&key]).await? else {
+            continue;
+        };
+        match progress(pool, cfg, &order, transfer).await {
+            Ok(true) => progressed += 1,
+            Ok(false) => {}
+            Err(e) => warn!(order_id = order.id, tx = %transfer.tx, error = %e, "failed to progress order"),
+        }
+    }
+
+    for key in recently_closed_addresses(pool).await? {
+        fetch_transfers(indexer, &mut transfers_cache, &key).await;
+    }
+    for transfer in transfers_cache.values().flatten() {
+        if let Err(e) = record_if_unmatched(pool, cfg, transfer, SOURCE_WATCHER).await {
+            warn!(tx = %transfer.tx, error = %e, "failed to record unmatched transfer");
+        }
+    }
+    Ok(progressed)
+}
+
+/// 管理端查询未匹配转账，默认只看待处理的
+pub async fn unmatched_page(pool: &SqlitePool, query: UnmatchedTransferQuery) -> Result<PageVO<UnmatchedTransferVO>> {
+    let limit = query.size.filter(|s| *s > 0).unwrap_or(20);
+    let page = query.page.filter(|p| *p > 0).unwrap_or(1);
+    let offset = (page - 1) * limit;
+    let status = query.status.unwrap_or_else(|| "open".to_string());
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(1) as "count!: i64" FROM t_unmatched_transfer WHERE status = ?1"#,
+        status
+    ).fetch_one(pool).await.context("查询未匹配转账失败")?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            id as "id!: i64",
+            network,
+            tx,
+            from_address,
+            to_address,
+            amount,
+            block_time as "block_time: NaiveDateTime",
+            order_id,
+            source,
+            status,
+            note,
+            resolved_by,
+            resolved_at as "resolved_at: NaiveDateTime",
+            created as "created: NaiveDateTime"
+        FROM t_unmatched_transfer
+        WHERE status = ?1
+        ORDER BY created DESC
+        LIMIT ?2 OFFSET ?3
+        "#,
+        status,
+        limit,
+        offset
+    ).fetch_all(pool).await.context("查询未匹配转账失败")?;
+
+    let items = rows
+        .into_iter()
+        .map(|r| UnmatchedTransferVO {
+            id: r.id.to_string(),
+            network: r.network,
+            tx: r.tx,
+            from_address: r.from_address,
+            to_address: r.to_address,
+            amount: money::parse(&r.amount).unwrap_or_default(),
+            block_time: r.block_time.and_utc(),
+            order_id: r.order_id.map(|id| id.to_string()),
+            source: r.source,
+            status: r.status,
+            note: r.note,
+            resolved_by: r.resolved_by,
+            resolved_at: r.resolved_at.map(|t| t.and_utc()),
+            created: r.created.and_utc(),
+        })
+        .collect();
+    Ok(PageVO { items, total, page, size: limit })
+}
+
+/// 标记未匹配转账已人工处理（如已退回或已手动开通）
+pub async fn resolve_unmatched(pool: &SqlitePool, id: i64, actor: &str, note: &str) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query!(
+        r#"
+        UPDATE t_unmatched_transfer SET status = 'resolved', note = ?1, resolved_by = ?2, resolved_at = ?3
+        WHERE id = ?4 AND status = 'open'
+        "#,
+        note,
+        actor,
+        now,
+        id
+    ).execute(pool).await.context("更新未匹配转账失败")?;
+    if result.rows_affected() == 0 {
+        bail!("记录不存在或已处理");
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+use crate::models::plan::{PlanPriceVO, PlanVO};
+use crate::models::subscription::PlanType;
+use crate::utils::money;
+
+/// 查询套餐在指定网络上的当前售价（已上架且处于生效区间，取最近生效的一条）
+pub async fn current_price(
+    pool: &SqlitePool,
+    plan_type: PlanType,
+    network: &str,
+) -> anyhow::Result<Option<PlanPriceVO>> {
+    let now = Utc::now().naive_utc();
+    let plan = plan_type.as_str();
+    let row = sqlx::query!(
+        r#"
+        SELECT network, currency, amount
+        FROM t_plan_price
+        WHERE plan_type = ?1 AND network = ?2 AND enabled = 1
+          AND effective_from <= ?3 AND (effective_to IS NULL OR effective_to > ?3)
+        ORDER BY effective_from DESC, id DESC
+        LIMIT 1
+        "#,
+        plan,
+        network,
+        now
+    ).fetch_optional(pool).await.context("查询套餐价格失败")?;
+    row.map(|r| {
+        Ok(PlanPriceVO { amount: money::parse(&r.amount)?, network: r.network, currency: r.currency })
+    }).transpose()
+}
+
+/// 当前在售的套餐及各网络价格
+pub async fn list(pool: &SqlitePool) -> anyhow::Result<Vec<PlanVO>> {
+    let now = Utc::now().naive_utc();
+    // 每个 (套餐, 网络) 只取最近生效的一条
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.plan_type, p.network, p.currency, p.amount
+        FROM t_plan_price p
+        WHERE p.enabled = 1
+          AND p.effective_from <= ?1 AND (p.effective_to IS NULL OR p.effective_to > ?1)
+          AND p.id = (
+              SELECT q.id FROM t_plan_price q
+              WHERE q.plan_type = p.plan_type AND q.network = p.network AND q.enabled = 1
+                AND q.effective_from <= ?1 AND (q.effective_to IS NULL OR q.effective_to > ?1)
+              ORDER BY q.effective_from DESC, q.id DESC
+              LIMIT 1
+          )
+        ORDER BY p.network ASC
+        "#,
+        now
+    ).fetch_all(pool).await.context("查询套餐列表失败")?;
+
+    let mut plans: Vec<PlanVO> = Vec::new();
+    for r in rows {
+        let Some(plan_type) = PlanType::parse(&r.plan_type) else {
+            continue;
+        };
+        let price = PlanPriceVO { amount: money::parse(&r.amount)?, network: r.network, currency: r.currency };
+        match plans.iter_mut().find(|p| p.plan_type == plan_type) {
+            Some(plan) => plan.prices.push(price),
+            None => plans.push(PlanVO { plan_type, vip: plan_type.vip().to_string(), prices: vec![price] }),
+        }
+    }
+    plans.sort_by_key(|p| p.plan_type);
+    Ok(plans)
+}
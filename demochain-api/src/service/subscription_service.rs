@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use chrono::{Months, NaiveDateTime, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::models::subscription::{EntitlementVO, PlanType};
+
+/// 订单确认后发放权益；需在确认订单的同一事务内调用，保证订单状态与权益一致
+pub async fn grant_for_order(conn: &mut SqliteConnection, order_id: i64) -> Result<()> {
+    let order = sqlx::query!(
+        "SELECT user_id, plan_type FROM t_order WHERE id = ?1",
+        order_id
+    ).fetch_one(&mut *conn).await.context("查询订单失败")?;
+    let plan = PlanType::parse(&order.plan_type).context("不支持的套餐")?;
+    let now = Utc::now().naive_utc();
+
+    // 续费顺延：新的一期从该用户当前最晚到期时间开始
+    let latest = sqlx::query!(
+        r#"SELECT MAX(expires) as "latest?: NaiveDateTime" FROM t_subscription WHERE user_id = ?1 AND expires > ?2 AND revoked_at IS NULL"#,
+        order.user_id,
+        now
+    ).fetch_one(&mut *conn).await.context("查询订阅失败")?;
+    let starts = latest.latest.filter(|l| *l > now).unwrap_or(now);
+    let expires = match plan {
+        PlanType::Monthly => Some(starts.checked_add_months(Months::new(1)).context("到期时间溢出")?),
+        PlanType::Yearly => Some(starts.checked_add_months(Months::new(12)).context("到期时间溢出")?),
+        PlanType::Lifetime => None,
+    };
+    let plan_type = plan.as_str();
+
+    sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO t_subscription (user_id, order_id, plan_type, starts, expires, created)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        order.user_id,
+        order_id,
+        plan_type,
+        starts,
+        expires,
+        now
+    ).execute(&mut *conn).await.context("发放权益失败")?;
+    Ok(())
+}
+
+/// 订单退款后撤销其权益，并把之后顺延的各期提前，补上被撤销的未使用时长；
+/// 需在退款的同一事务内调用
+pub async fn revoke_for_order(conn: &mut SqliteConnection, order_id: i64) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let sub = sqlx::query!(
+        r#"
+        UPDATE t_subscription SET revoked_at = ?1
+        WHERE order_id = ?2 AND revoked_at IS NULL
+        RETURNING user_id, starts as "starts: NaiveDateTime", expires as "expires: NaiveDateTime"
+        "#,
+        now,
+        order_id
+    ).fetch_optional(&mut *conn).await.context("撤销权益失败")?;
+    let Some(sub) = sub else {
+        return Ok(());
+    };
+    // 永久套餐或已过期的一期无需调整后续
+    let Some(expires) = sub.expires.filter(|e| *e > now) else {
+        return Ok(());
+    };
+    let unused = expires - sub.starts.max(now);
+
+    let later = sqlx::query!(
+        r#"
+        SELECT id as "id!: i64", starts as "starts: NaiveDateTime", expires as "expires: NaiveDateTime"
+        FROM t_subscription
+        WHERE user_id = ?1 AND starts >= ?2 AND revoked_at IS NULL
+        "#,
+        sub.user_id,
+        expires
+    ).fetch_all(&mut *conn).await.context("查询订阅失败")?;
+    for s in later {
+        let starts = s.starts - unused;
+        let expires = s.expires.map(|e| e - unused);
+        sqlx::query!(
+            "UPDATE t_subscription SET starts = ?1, expires = ?2 WHERE id = ?3",
+            starts,
+            expires,
+            s.id
+        ).execute(&mut *conn).await.context("调整订阅失败")?;
+    }
+    Ok(())
+}
+
+/// 用户当前生效的权益，没有时返回 None（免费用户）
+pub async fn current(pool: &SqlitePool, user_id: &str) -> Result<Option<EntitlementVO>> {
+    let now = Utc::now().naive_utc();
+    let rows = sqlx::query!(
+        r#"
+        SELECT plan_type
+        FROM t_subscription
+        WHERE user_id = ?1 AND starts <= ?2 AND (expires IS NULL OR expires > ?2) AND revoked_at IS NULL
+        "#,
+        user_id,
+        now
+    ).fetch_all(pool).await.context("查询订阅失败")?;
+    let Some(plan) = rows.iter().filter_map(|r| PlanType::parse(&r.plan_type)).max() else {
+        return Ok(None);
+    };
+
+    let expires = if plan == PlanType::Lifetime {
+        None
+    } else {
+        sqlx::query!(
+            r#"SELECT MAX(expires) as "latest?: NaiveDateTime" FROM t_subscription WHERE user_id = ?1 AND expires > ?2 AND revoked_at IS NULL"#,
+            user_id,
+            now
+        ).fetch_one(pool).await.context("查询订阅失败")?.latest.map(|e| e.and_utc())
+    };
+
+    Ok(Some(EntitlementVO {
+        plan_type: plan,
+        vip: plan.vip().to_string(),
+        expires,
+    }))
+}
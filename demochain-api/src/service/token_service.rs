@@ -0,0 +1,259 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use sqlx::SqlitePool;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use crate::models::user::Claims;
+use crate::utils::jwt_util::JwtService;
+use crate::utils::token;
+
+pub const PASSWORD_RESET_EXPIRY_SECONDS: i64 = 30 * 60; // 30 minutes
+
+/// 签发刷新令牌；family_id 为空时开启新的令牌家族（对应一次新的登录）
+pub async fn issue_refresh_token(
+    pool: &SqlitePool,
+    user_id: &str,
+    family_id: Option<&str>,
+) -> Result<String> {
+    let refresh_token = token::generate_opaque_token();
+    let token_hash = token::hash_token(&refresh_token);
+    let family_id = family_id
+        .map(|f| f.to_string())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let now = Utc::now().naive_utc();
+    let expires = now + Duration::seconds(JwtService::refresh_token_ttl());
+
+    sqlx::query!(
+        r#"
+        INSERT INTO t_refresh_token (user_id, family_id, token_hash, revoked, expires, created)
+        VALUES (?1, ?2, ?3, 0, ?4, ?5)
+        "#,
+        user_id,
+        family_id,
+        token_hash,
+        expires,
+        now
+    ).execute(pool).await.context("插入刷新令牌失败")?;
+
+    Ok(refresh_token)
+}
+
+/// 轮换刷新令牌，返回 (user_id, 新刷新令牌)
+///
+/// 每个刷新令牌只能使用一次；已使用过的令牌再次出现说明可能被盗用，
+/// 此时吊销整个令牌家族，迫使持有者重新登录。
+pub async fn rotate_refresh_token(pool: &SqlitePool, refresh_token: &str) -> Result<(String, String)> {
+    let token_hash = token::hash_token(refresh_token);
+    let now = Utc::now().naive_utc();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            id as "id!: i64",
+            user_id,
+            family_id,
+            revoked,
+            expires as "expires: NaiveDateTime"
+        FROM t_refresh_token
+        WHERE token_hash = ?1
+        "#,
+        token_hash
+    ).fetch_optional(pool).await.context("查询刷新令牌失败")?;
+
+    let row = row.ok_or_else(|| anyhow!("刷新令牌无效"))?;
+    if row.revoked != 0 {
+        warn!(user_id = %row.user_id, family_id = %row.family_id, "refresh token reused, revoking family");
+        revoke_family(pool, &row.family_id).await?;
+        bail!("刷新令牌已失效，请重新登录");
+    }
+    if row.expires < now {
+        bail!("刷新令牌已过期，请重新登录");
+    }
+
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    // 条件更新保证并发请求中只有一个能完成轮换
+    let consumed = sqlx::query!(
+        "UPDATE t_refresh_token SET revoked = 1 WHERE id = ?1 AND revoked = 0",
+        row.id
+    ).execute(&mut *tx).await.context("更新刷新令牌失败")?;
+    if consumed.rows_affected() != 1 {
+        tx.rollback().await.ok();
+        warn!(user_id = %row.user_id, family_id = %row.family_id, "refresh token raced, revoking family");
+        revoke_family(pool, &row.family_id).await?;
+        bail!("刷新令牌已失效，请重新登录");
+    }
+
+    let new_token = token::generate_opaque_token();
+    let new_hash = token::hash_token(&new_token);
+    let expires = now + Duration::seconds(JwtService::refresh_token_ttl());
+    sqlx::query!(
+        r#"
+        INSERT INTO t_refresh_token (user_id, family_id, token_hash, revoked, expires, created)
+        VALUES (?1, ?2, ?3, 0, ?4, ?5)
+        "#,
+        row.user_id,
+        row.family_id,
+        new_hash,
+        expires,
+        now
+    ).execute(&mut *tx).await.context("插入刷新令牌失败")?;
+    tx.commit().await.context("提交事务失败")?;
+
+    Ok((row.user_id, new_token))
+}
+
+/// 吊销整个令牌家族
+pub async fn revoke_family(pool: &SqlitePool, family_id: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE t_refresh_token SET revoked = 1 WHERE family_id = ?1",
+        family_id
+    ).execute(pool).await.context("吊销刷新令牌失败")?;
+    Ok(())
+}
+
+/// 吊销某个刷新令牌所在的家族（登出时使用，只允许吊销自己的令牌）
+pub async fn revoke_refresh_token(pool: &SqlitePool, user_id: &str, refresh_token: &str) -> Result<()> {
+    let token_hash = token::hash_token(refresh_token);
+    let row = sqlx::query!(
+        "SELECT family_id FROM t_refresh_token WHERE token_hash = ?1 AND user_id = ?2",
+        token_hash,
+        user_id
+    ).fetch_optional(pool).await.context("查询刷新令牌失败")?;
+    if let Some(row) = row {
+        revoke_family(pool, &row.family_id).await?;
+    }
+    Ok(())
+}
+
+/// 将访问令牌加入吊销表，保留到其原本的过期时间
+pub async fn revoke_access_token(pool: &SqlitePool, jti: &str, user_id: &str, exp: i64) -> Result<()> {
+    let expires = DateTime::<Utc>::from_timestamp(exp, 0)
+        .ok_or_else(|| anyhow!("令牌过期时间无效"))?
+        .naive_utc();
+    let now = Utc::now().naive_utc();
+    sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO t_token_denylist (jti, user_id, expires, created)
+        VALUES (?1, ?2, ?3, ?4)
+        "#,
+        jti,
+        user_id,
+        expires,
+        now
+    ).execute(pool).await.context("吊销访问令牌失败")?;
+    Ok(())
+}
+
+/// 令牌是否已失效：jti 在吊销表中、签发时间早于用户的 token_valid_after（毫秒），或账号已被禁用
+pub async fn is_token_revoked(pool: &SqlitePool, claims: &Claims) -> Result<bool> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS(SELECT 1 FROM t_token_denylist WHERE jti = ?1) as "denied!: bool",
+            (SELECT token_valid_after FROM t_user WHERE id = ?2) as "valid_after?: i64",
+            EXISTS(SELECT 1 FROM t_user WHERE id = ?2 AND disabled_at IS NOT NULL) as "disabled!: bool"
+        "#,
+        claims.jti,
+        claims.sub
+    ).fetch_one(pool).await.context("查询吊销表失败")?;
+    Ok(row.denied || row.disabled || claims.issued_at_millis() < row.valid_after.unwrap_or(0))
+}
+
+/// 吊销用户的全部刷新令牌
+pub async fn revoke_user_refresh_tokens(pool: &SqlitePool, user_id: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE t_refresh_token SET revoked = 1 WHERE user_id = ?1",
+        user_id
+    ).execute(pool).await.context("吊销刷新令牌失败")?;
+    Ok(())
+}
+
+/// 签发密码重置令牌，同时作废该用户之前未使用的重置令牌
+pub async fn issue_password_reset(pool: &SqlitePool, user_id: &str) -> Result<String> {
+    let reset_token = token::generate_opaque_token();
+    let token_hash = token::hash_token(&reset_token);
+    let now = Utc::now().naive_utc();
+    let expires = now + Duration::seconds(PASSWORD_RESET_EXPIRY_SECONDS);
+
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    sqlx::query!(
+        "UPDATE t_password_reset SET used = ?1 WHERE user_id = ?2 AND used IS NULL",
+        now,
+        user_id
+    ).execute(&mut *tx).await.context("作废旧重置令牌失败")?;
+    sqlx::query!(
+        r#"
+        INSERT INTO t_password_reset (user_id, token_hash, expires, used, created)
+        VALUES (?1, ?2, ?3, NULL, ?4)
+        "#,
+        user_id,
+        token_hash,
+        expires,
+        now
+    ).execute(&mut *tx).await.context("插入重置令牌失败")?;
+    tx.commit().await.context("提交事务失败")?;
+
+    Ok(reset_token)
+}
+
+/// 消费密码重置令牌，返回 user_id；令牌不存在、已使用或已过期时报错
+pub async fn consume_password_reset(pool: &SqlitePool, reset_token: &str) -> Result<String> {
+    let token_hash = token::hash_token(reset_token);
+    let now = Utc::now().naive_utc();
+
+    let row = sqlx::query!(
+        r#"
+        SELECT id as "id!: i64", user_id
+        FROM t_password_reset
+        WHERE token_hash = ?1 AND used IS NULL AND expires > ?2
+        "#,
+        token_hash,
+        now
+    ).fetch_optional(pool).await.context("查询重置令牌失败")?;
+    let row = row.ok_or_else(|| anyhow!("重置链接无效或已过期"))?;
+
+    // 条件更新保证令牌只能被使用一次
+    let consumed = sqlx::query!(
+        "UPDATE t_password_reset SET used = ?1 WHERE id = ?2 AND used IS NULL",
+        now,
+        row.id
+    ).execute(pool).await.context("更新重置令牌失败")?;
+    if consumed.rows_affected() != 1 {
+        bail!("重置链接无效或已过期");
+    }
+
+    Ok(row.user_id)
+}
+
+/// 清理已过期的吊销记录、刷新令牌和重置令牌，返回删除的行数
+pub async fn purge_expired(pool: &SqlitePool) -> Result<u64> {
+    let now = Utc::now().naive_utc();
+    let denied = sqlx::query!("DELETE FROM t_token_denylist WHERE expires < ?1", now)
+        .execute(pool)
+        .await
+        .context("清理吊销表失败")?;
+    let refresh = sqlx::query!("DELETE FROM t_refresh_token WHERE expires < ?1", now)
+        .execute(pool)
+        .await
+        .context("清理刷新令牌失败")?;
+    let reset = sqlx::query!("DELETE FROM t_password_reset WHERE expires < ?1", now)
+        .execute(pool)
+        .await
+        .context("清理重置令牌失败")?;
+    Ok(denied.rows_affected() + refresh.rows_affected() + reset.rows_affected())
+}
+
+/// 后台定时清理过期令牌
+pub fn spawn_cleanup_task(pool: SqlitePool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            ticker.tick().await;
+            match purge_expired(&pool).await {
+                Ok(0) => {}
+                Ok(n) => info!(rows = n, "purged expired tokens"),
+                Err(e) => error!(error = %e, "failed to purge expired tokens"),
+            }
+        }
+    });
+}
@@ -1,13 +1,25 @@
 use sqlx::SqlitePool;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use anyhow::{bail, Context, Result};
-use crate::models::user::{AuthVO, LoginDTO, RegisterDTO, User, UserDetail};
-use crate::utils::{jwt_util::JwtService, password::PasswordService};
+use crate::models::user::{
+    AuthVO, ChangePasswordRequest, Claims, ConfirmResetPasswordRequest, LoginDTO, LogoutDTO, RefreshTokenDTO,
+    RegisterDTO, ResetPasswordRequest, TokenVO, User, UserDetail, VerifyEmailRequest,
+};
+use crate::models::subscription::VIP_FREE;
+use crate::service::{subscription_service, token_service};
+use crate::utils::jwt_util::{JwtService, EMAIL_VERIFY_EXPIRY_SECONDS};
+use crate::utils::mailer::{Mail, Mailer};
+use crate::utils::password::PasswordService;
+use std::sync::Arc;
+use tracing::error;
 
 pub async fn register(
     pool: &SqlitePool,
+    mailer: Arc<dyn Mailer>,
+    public_url: &str,
     payload: RegisterDTO,
 ) -> Result<()> {
+    validate_email(&payload.email)?;
     let existing = sqlx::query!("SELECT id FROM t_user WHERE email = ?1",payload.email)
         .fetch_optional(pool)
         .await
@@ -20,15 +32,16 @@ pub async fn register(
     let password = PasswordService::hash_password(&payload.password)?;
     let username = payload.email.split('@').next().unwrap_or("").to_string();
 
-    sqlx::query!(r#"
+    let user_id = sqlx::query!(r#"
         INSERT INTO t_user (email,username, password)
         VALUES (?1, ?2, ?3)
         "#,
         payload.email,
         username,
         password
-    ).execute(pool).await.with_context(|| "failed to insert user")?;
+    ).execute(pool).await.with_context(|| "failed to insert user")?.last_insert_rowid();
 
+    send_verification_mail(mailer, public_url, user_id.to_string(), payload.email)?;
     Ok(())
 }
 
@@ -36,6 +49,35 @@ pub async fn login(
     pool: &SqlitePool,
     payload: LoginDTO,
 ) -> Result<AuthVO> {
+    let user = find_by_email(pool, &payload.email).await?;
+    let user = user.ok_or_else(|| anyhow::anyhow!("邮箱或密码错误"))?;
+
+    // 验证密码（User.password 为 Option<String>）
+    match &user.password {
+        Some(hashed) => {
+            if !PasswordService::verify_password(&payload.password, hashed)? {
+                bail!("邮箱或密码错误");
+            }
+        }
+        None => bail!("邮箱或密码错误"),
+    }
+    if user.disabled_at.is_some() {
+        bail!("账号已被禁用");
+    }
+
+    let token = JwtService::generate_token(user.id.clone(), user.email.clone(), user.role.clone())?;
+    let refresh_token = token_service::issue_refresh_token(pool, &user.id, None).await?;
+
+    Ok(AuthVO {
+        token,
+        refresh_token,
+        user_detail: user_detail(pool, user).await?,
+        expires_in: Some(JwtService::token_ttl()),
+    })
+}
+
+/// 当前用户信息，vip 与权益由订阅表推导
+pub async fn me(pool: &SqlitePool, user_id: &str) -> Result<UserDetail> {
     let user = sqlx::query_as!(
         User,
         r#"
@@ -44,37 +86,323 @@ pub async fn login(
             email,
             username,
             password as "password?: String",
+            role,
+            verified_at as "verified_at: DateTime<Utc>",
+            disabled_at as "disabled_at: DateTime<Utc>",
+            created as "created: DateTime<Utc>",
+            updated as "updated: DateTime<Utc>"
+        FROM t_user WHERE id = ?1
+        "#,
+        user_id
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    let user = user.ok_or_else(|| anyhow::anyhow!("用户不存在"))?;
+    user_detail(pool, user).await
+}
+
+async fn find_by_email(pool: &SqlitePool, email: &str) -> Result<Option<User>> {
+    sqlx::query_as!(
+        User,
+        r#"
+        SELECT
+            CAST(id AS TEXT) as "id!: String",
+            email,
+            username,
+            password as "password?: String",
+            role,
+            verified_at as "verified_at: DateTime<Utc>",
+            disabled_at as "disabled_at: DateTime<Utc>",
             created as "created: DateTime<Utc>",
             updated as "updated: DateTime<Utc>"
         FROM t_user WHERE email = ?1
         "#,
-        payload.email
+        email
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")
+}
+
+async fn user_detail(pool: &SqlitePool, user: User) -> Result<UserDetail> {
+    let entitlement = subscription_service::current(pool, &user.id).await?;
+    Ok(UserDetail {
+        vip: entitlement.as_ref().map(|e| e.vip.clone()).unwrap_or_else(|| VIP_FREE.to_string()),
+        role: user.role,
+        id: user.id,
+        email: user.email,
+        username: user.username,
+        email_verified: user.verified_at.is_some(),
+        entitlement,
+        created: user.created,
+        updated: user.updated,
+    })
+}
+
+pub async fn refresh(
+    pool: &SqlitePool,
+    payload: RefreshTokenDTO,
+) -> Result<TokenVO> {
+    let (user_id, refresh_token) = token_service::rotate_refresh_token(pool, &payload.refresh_token).await?;
+
+    let user = sqlx::query!(
+        r#"SELECT email, role, disabled_at IS NOT NULL as "disabled!: bool" FROM t_user WHERE id = ?1"#,
+        user_id
     ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    let user = user.ok_or_else(|| anyhow::anyhow!("用户不存在"))?;
+    if user.disabled {
+        bail!("账号已被禁用");
+    }
 
-    let user = user.ok_or_else(|| anyhow::anyhow!("邮箱或密码错误"))?;
+    let token = JwtService::generate_token(user_id, user.email, user.role)?;
 
-    // 验证密码（User.password 为 Option<String>）
-    match &user.password {
-        Some(hashed) => {
-            if !PasswordService::verify_password(&payload.password, hashed)? {
-                bail!("邮箱或密码错误");
-            }
-        }
-        None => bail!("邮箱或密码错误"),
+    Ok(TokenVO {
+        token,
+        refresh_token,
+        expires_in: JwtService::token_ttl(),
+    })
+}
+
+pub async fn logout(
+    pool: &SqlitePool,
+    claims: &Claims,
+    payload: Option<LogoutDTO>,
+) -> Result<()> {
+    token_service::revoke_access_token(pool, &claims.jti, &claims.sub, claims.exp).await?;
+    if let Some(refresh_token) = payload.and_then(|p| p.refresh_token) {
+        token_service::revoke_refresh_token(pool, &claims.sub, &refresh_token).await?;
     }
+    Ok(())
+}
 
-    let token = JwtService::generate_token(user.id.clone(), user.email.clone())?;
+pub async fn change_password(
+    pool: &SqlitePool,
+    claims: &Claims,
+    payload: ChangePasswordRequest,
+) -> Result<TokenVO> {
+    let user = sqlx::query!(
+        r#"SELECT password, role FROM t_user WHERE id = ?1"#,
+        claims.sub
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    let user = user.ok_or_else(|| anyhow::anyhow!("用户不存在"))?;
 
-    Ok(AuthVO {
+    if !PasswordService::verify_password(&payload.old_password, &user.password)? {
+        bail!("原密码错误");
+    }
+    if payload.old_password == payload.new_password {
+        bail!("新密码不能与原密码相同");
+    }
+    PasswordService::validate_password_strength(&payload.new_password)?;
+    update_password(pool, &claims.sub, &payload.new_password).await?;
+    token_service::revoke_access_token(pool, &claims.jti, &claims.sub, claims.exp).await?;
+
+    // 当前会话换发新令牌，避免修改密码后被立即登出
+    let token = JwtService::generate_token(claims.sub.clone(), claims.email.clone(), user.role)?;
+    let refresh_token = token_service::issue_refresh_token(pool, &claims.sub, None).await?;
+    Ok(TokenVO {
         token,
-        user_detail: UserDetail {
-            id: user.id.clone(),
-            email: user.email.clone(),
-            vip: "1".to_string(), // 默认免费用户
-            username: user.username.clone(),
-            created: user.created,
-            updated: user.updated,
-        },
-        expires_in: Some(24 * 60 * 60),
+        refresh_token,
+        expires_in: JwtService::token_ttl(),
     })
 }
+
+/// 发起密码重置；无论邮箱是否注册都返回成功，避免泄露注册信息。
+/// 查询、签发令牌与发信都在后台完成，响应耗时与邮箱是否注册无关
+pub async fn request_password_reset(
+    pool: &SqlitePool,
+    mailer: Arc<dyn Mailer>,
+    public_url: &str,
+    payload: ResetPasswordRequest,
+) -> Result<()> {
+    let pool = pool.clone();
+    let public_url = public_url.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = send_password_reset(&pool, mailer, &public_url, &payload.email).await {
+            error!(error = %e, "failed to send password reset mail");
+        }
+    });
+    Ok(())
+}
+
+async fn send_password_reset(
+    pool: &SqlitePool,
+    mailer: Arc<dyn Mailer>,
+    public_url: &str,
+    email: &str,
+) -> Result<()> {
+    let user = sqlx::query!(
+        r#"SELECT CAST(id AS TEXT) as "id!: String", email FROM t_user WHERE email = ?1"#,
+        email
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    let reset_token = token_service::issue_password_reset(pool, &user.id).await?;
+    mailer.send(Mail {
+        to: user.email,
+        subject: "DemoChain 密码重置".to_string(),
+        body: format!(
+            "您正在重置 DemoChain 账号密码，请在 {} 分钟内打开以下链接完成操作：\n\n{}/reset-password?token={}\n\n如果这不是您本人的操作，请忽略此邮件。",
+            token_service::PASSWORD_RESET_EXPIRY_SECONDS / 60,
+            public_url,
+            reset_token
+        ),
+    }).await
+}
+
+pub async fn reset_password(
+    pool: &SqlitePool,
+    payload: ConfirmResetPasswordRequest,
+) -> Result<()> {
+    // 先校验密码强度，避免弱密码白白消耗一次性令牌
+    PasswordService::validate_password_strength(&payload.new_password)?;
+    let user_id = token_service::consume_password_reset(pool, &payload.token).await?;
+    update_password(pool, &user_id, &payload.new_password).await
+}
+
+/// 更新密码并推进 token_valid_after，之前签发的访问令牌和刷新令牌全部失效；
+/// 调用方负责校验密码强度
+async fn update_password(pool: &SqlitePool, user_id: &str, new_password: &str) -> Result<()> {
+    let password = PasswordService::hash_password(new_password)?;
+    let now = Utc::now();
+    let now_naive = now.naive_utc();
+    let valid_after = now.timestamp_millis();
+
+    sqlx::query!(
+        r#"
+        UPDATE t_user SET password = ?1, updated = ?2, token_valid_after = ?3
+        WHERE id = ?4
+        "#,
+        password,
+        now_naive,
+        valid_after,
+        user_id
+    ).execute(pool).await.with_context(|| "failed to update password")?;
+    token_service::revoke_user_refresh_tokens(pool, user_id).await
+}
+
+pub async fn verify_email(
+    pool: &SqlitePool,
+    payload: VerifyEmailRequest,
+) -> Result<()> {
+    let claims = JwtService::verify_email_token(&payload.token)
+        .map_err(|_| anyhow::anyhow!("验证链接无效或已过期"))?;
+    let now = Utc::now().naive_utc();
+    // 邮箱已变更的旧链接不再生效；重复点击视为成功
+    let user = sqlx::query!(
+        r#"SELECT verified_at as "verified_at: NaiveDateTime" FROM t_user WHERE id = ?1 AND email = ?2"#,
+        claims.sub,
+        claims.email
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    let user = user.ok_or_else(|| anyhow::anyhow!("验证链接无效或已过期"))?;
+    if user.verified_at.is_none() {
+        sqlx::query!(
+            "UPDATE t_user SET verified_at = ?1, updated = ?1 WHERE id = ?2",
+            now,
+            claims.sub
+        ).execute(pool).await.with_context(|| "failed to update user")?;
+    }
+    Ok(())
+}
+
+pub async fn resend_verification(
+    pool: &SqlitePool,
+    mailer: Arc<dyn Mailer>,
+    public_url: &str,
+    claims: &Claims,
+) -> Result<()> {
+    let user = sqlx::query!(
+        r#"SELECT email, verified_at as "verified_at: NaiveDateTime" FROM t_user WHERE id = ?1"#,
+        claims.sub
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    let user = user.ok_or_else(|| anyhow::anyhow!("用户不存在"))?;
+    if user.verified_at.is_some() {
+        bail!("邮箱已验证");
+    }
+    send_verification_mail(mailer, public_url, claims.sub.clone(), user.email)
+}
+
+/// 需要已验证邮箱的操作（如付费下单）调用
+pub async fn ensure_email_verified(pool: &SqlitePool, user_id: &str) -> Result<()> {
+    let user = sqlx::query!(
+        r#"SELECT verified_at as "verified_at: NaiveDateTime" FROM t_user WHERE id = ?1"#,
+        user_id
+    ).fetch_optional(pool).await.with_context(|| "failed to query user")?;
+    match user {
+        Some(u) if u.verified_at.is_some() => Ok(()),
+        _ => bail!("请先完成邮箱验证"),
+    }
+}
+
+/// 管理端禁用账号：记录原因并吊销该用户的全部令牌
+pub async fn disable(pool: &SqlitePool, operator_id: &str, user_id: &str, reason: Option<&str>) -> Result<()> {
+    if operator_id == user_id {
+        bail!("不能禁用自己的账号");
+    }
+    let now = Utc::now();
+    let now_naive = now.naive_utc();
+    let valid_after = now.timestamp_millis();
+    let result = sqlx::query!(
+        r#"
+        UPDATE t_user SET disabled_at = ?1, disabled_reason = ?2, token_valid_after = ?3, updated = ?1
+        WHERE id = ?4
+        "#,
+        now_naive,
+        reason,
+        valid_after,
+        user_id
+    ).execute(pool).await.with_context(|| "failed to disable user")?;
+    if result.rows_affected() == 0 {
+        bail!("用户不存在");
+    }
+    token_service::revoke_user_refresh_tokens(pool, user_id).await
+}
+
+/// 管理端解除禁用
+pub async fn enable(pool: &SqlitePool, user_id: &str) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query!(
+        "UPDATE t_user SET disabled_at = NULL, disabled_reason = NULL, updated = ?1 WHERE id = ?2",
+        now,
+        user_id
+    ).execute(pool).await.with_context(|| "failed to enable user")?;
+    if result.rows_affected() == 0 {
+        bail!("用户不存在");
+    }
+    Ok(())
+}
+
+fn send_verification_mail(mailer: Arc<dyn Mailer>, public_url: &str, user_id: String, email: String) -> Result<()> {
+    let token = JwtService::generate_email_token(user_id, email.clone())?;
+    let mail = Mail {
+        to: email,
+        subject: "DemoChain 邮箱验证".to_string(),
+        body: format!(
+            "欢迎注册 DemoChain，请在 {} 天内打开以下链接完成邮箱验证：\n\n{}/verify-email?token={}\n\n如果这不是您本人的操作，请忽略此邮件。",
+            EMAIL_VERIFY_EXPIRY_SECONDS / (24 * 60 * 60),
+            public_url,
+            token
+        ),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(mail).await {
+            error!(error = %e, "failed to send verification mail");
+        }
+    });
+    Ok(())
+}
+
+fn validate_email(email: &str) -> Result<()> {
+    let valid = email.len() <= 254
+        && !email.chars().any(char::is_whitespace)
+        && match email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+            }
+            None => false,
+        };
+    if !valid {
+        bail!("邮箱格式不正确");
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use lru::LruCache;
+use sqlx::SqlitePool;
+use tracing::{debug, error};
+
+/// 文章阅读计数：同一访客在去重窗口内重复访问只计一次；
+/// 增量先累积在内存中，由后台任务定期批量写入，热门文章不会每次请求都写库。
+/// 进程退出时尚未写入的增量会丢失（最多一个写入周期）。
+/// 去重记录有容量上限，超出时淘汰最早的记录（被淘汰的访客再次访问会重新计数）
+pub struct ViewCounter {
+    window: Duration,
+    state: Mutex<ViewState>,
+}
+
+struct ViewState {
+    // (文章ID, 访客) -> 最近一次计数时间；按计数先后排列，最早的在 LRU 端
+    seen: LruCache<(i64, String), Instant>,
+    // 文章ID -> 尚未写库的增量
+    pending: HashMap<i64, i64>,
+}
+
+impl ViewCounter {
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            window,
+            state: Mutex::new(ViewState { seen: LruCache::new(capacity), pending: HashMap::new() }),
+        }
+    }
+
+    /// 记录一次访问；返回该文章尚未写库的增量（用于展示）
+    pub fn record(&self, article_id: i64, viewer: &str) -> i64 {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (article_id, viewer.to_string());
+        // peek 不调整顺序，保证 LRU 端始终是最早计数的记录
+        let counted = match state.seen.peek(&key) {
+            Some(last) if now.duration_since(*last) < self.window => false,
+            _ => {
+                state.seen.put(key, now);
+                true
+            }
+        };
+        let pending = state.pending.entry(article_id).or_insert(0);
+        if counted {
+            *pending += 1;
+        }
+        *pending
+    }
+
+    /// 把累积的增量写入数据库，同时清理已过去重窗口的访客记录；写入失败时增量放回，下次重试
+    pub async fn flush(&self, pool: &SqlitePool) -> anyhow::Result<usize> {
+        let pending = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let window = self.window;
+            while state.seen.peek_lru().is_some_and(|(_, last)| last.elapsed() >= window) {
+                state.seen.pop_lru();
+            }
+            std::mem::take(&mut state.pending)
+        };
+        let pending: Vec<(i64, i64)> = pending.into_iter().filter(|(_, n)| *n > 0).collect();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        match write(pool, &pending).await {
+            Ok(()) => Ok(pending.len()),
+            Err(e) => {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                for (id, n) in pending {
+                    *state.pending.entry(id).or_insert(0) += n;
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+async fn write(pool: &SqlitePool, pending: &[(i64, i64)]) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    for (id, n) in pending {
+        sqlx::query!(
+            "UPDATE t_article SET views = COALESCE(views, 0) + ?1 WHERE id = ?2",
+            n,
+            id
+        ).execute(&mut *tx).await.context("更新阅读数失败")?;
+    }
+    tx.commit().await.context("提交事务失败")?;
+    Ok(())
+}
+
+/// 定期写入阅读数
+pub fn spawn_flusher(pool: SqlitePool, counter: Arc<ViewCounter>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match counter.flush(&pool).await {
+                Ok(0) => {}
+                Ok(n) => debug!(articles = n, "flushed article views"),
+                Err(e) => error!(error = %e, "failed to flush article views"),
+            }
+        }
+    });
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::service::payment_service::{self, PaymentConfig};
+use crate::utils::chain_indexer::TokenTransfer;
+
+/// 请求体上限（路由层限制），正常的推送事件远小于该值
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+// 验签失败的请求只保存摘要与开头部分，避免任意请求写满数据库
+const REJECTED_PAYLOAD_CHARS: usize = 256;
+
+/// 支付 webhook 配置
+#[derive(Clone, Default)]
+pub struct WebhookConfig {
+    // provider -> HMAC 密钥；未配置的 provider 一律拒绝
+    pub secrets: HashMap<String, Vec<u8>>,
+    // 时间戳允许的偏差，超出视为重放
+    pub tolerance: Duration,
+}
+
+/// 推送事件：签名内容为 "{timestamp}.{原始请求体}"，HMAC-SHA256 十六进制
+#[derive(Debug, Deserialize)]
+struct WebhookEvent {
+    event_id: String,
+    transfer: TokenTransfer,
+}
+
+/// 请求头中的签名信息
+pub struct WebhookSignature<'a> {
+    pub timestamp: Option<&'a str>,
+    pub signature: Option<&'a str>,
+}
+
+/// 处理结果
+pub enum WebhookOutcome {
+    Processed(i64),
+    Unmatched,
+    Duplicate,
+    Rejected(String),
+    Invalid(String),
+    Failed(String),
+}
+
+impl WebhookOutcome {
+    fn status(&self) -> &'static str {
+        match self {
+            WebhookOutcome::Processed(_) => "processed",
+            WebhookOutcome::Unmatched => "unmatched",
+            WebhookOutcome::Duplicate => "duplicate",
+            WebhookOutcome::Rejected(_) => "rejected",
+            WebhookOutcome::Invalid(_) => "invalid",
+            WebhookOutcome::Failed(_) => "failed",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            WebhookOutcome::Processed(order_id) => format!("订单 {} 已更新", order_id),
+            WebhookOutcome::Unmatched => "未匹配到待支付订单".to_string(),
+            WebhookOutcome::Duplicate => "事件已处理".to_string(),
+            WebhookOutcome::Rejected(msg) | WebhookOutcome::Invalid(msg) | WebhookOutcome::Failed(msg) => msg.clone(),
+        }
+    }
+}
+
+/// 处理一次 webhook 推送：未配置的推送方直接拒绝不落库；
+/// 其余请求先验签再落库（验签失败只保存摘要），然后幂等检查并推进订单
+pub async fn handle(
+    pool: &SqlitePool,
+    cfg: &WebhookConfig,
+    payment: &PaymentConfig,
+    provider: &str,
+    sig: WebhookSignature<'_>,
+    body: &[u8],
+) -> Result<WebhookOutcome> {
+    if !cfg.secrets.contains_key(provider) {
+        return Ok(WebhookOutcome::Rejected("未知的推送方".to_string()));
+    }
+    let now = Utc::now().naive_utc();
+    let timestamp = sig.timestamp.and_then(|t| t.trim().parse::<i64>().ok());
+    let verified = verify(cfg, provider, timestamp, sig.signature, body);
+    let payload = match verified {
+        Ok(()) => String::from_utf8_lossy(body).into_owned(),
+        Err(_) => rejected_payload(body),
+    };
+    let webhook_id = sqlx::query!(
+        r#"
+        INSERT INTO t_payment_webhook (provider, signature, timestamp, payload, status, received)
+        VALUES (?1, ?2, ?3, ?4, 'received', ?5)
+        "#,
+        provider,
+        sig.signature,
+        timestamp,
+        payload,
+        now
+    ).execute(pool).await.context("记录 webhook 失败")?.last_insert_rowid();
+
+    let (outcome, event_id) = match verified {
+        Err(e) => (WebhookOutcome::Rejected(e.to_string()), None),
+        Ok(()) => match serde_json::from_slice::<WebhookEvent>(body) {
+            Err(e) => (WebhookOutcome::Invalid(format!("事件格式无效: {}", e)), None),
+            Ok(event) => {
+                let outcome = process(pool, payment, provider, webhook_id, &event).await?;
+                (outcome, Some(event.event_id))
+            }
+        },
+    };
+
+    let (status, message) = (outcome.status(), outcome.message());
+    let order_id = match outcome {
+        WebhookOutcome::Processed(id) => Some(id),
+        _ => None,
+    };
+    sqlx::query!(
+        "UPDATE t_payment_webhook SET event_id = ?1, status = ?2, message = ?3, order_id = ?4 WHERE id = ?5",
+        event_id,
+        status,
+        message,
+        order_id,
+        webhook_id
+    ).execute(pool).await.context("更新 webhook 记录失败")?;
+    info!(provider, webhook_id, status, "payment webhook handled");
+    Ok(outcome)
+}
+
+// 验签失败请求的存档内容：请求体哈希、长度与开头部分
+fn rejected_payload(body: &[u8]) -> String {
+    let head: String = String::from_utf8_lossy(body).chars().take(REJECTED_PAYLOAD_CHARS).collect();
+    format!("[sha256={} bytes={}] {}", hex::encode(Sha256::digest(body)), body.len(), head)
+}
+
+fn verify(cfg: &WebhookConfig, provider: &str, timestamp: Option<i64>, signature: Option<&str>, body: &[u8]) -> Result<()> {
+    let secret = cfg.secrets.get(provider).ok_or_else(|| anyhow!("未知的推送方"))?;
+    let timestamp = timestamp.ok_or_else(|| anyhow!("缺少时间戳"))?;
+    let skew = Utc::now().timestamp().abs_diff(timestamp);
+    if skew > cfg.tolerance.as_secs() {
+        return Err(anyhow!("时间戳超出允许范围"));
+    }
+    let signature = signature.ok_or_else(|| anyhow!("缺少签名"))?;
+    let signature = signature.trim();
+    let expected = hex::decode(signature.strip_prefix("sha256=").unwrap_or(signature))
+        .map_err(|_| anyhow!("签名格式无效"))?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).map_err(|_| anyhow!("密钥无效"))?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&expected).map_err(|_| anyhow!("签名校验失败"))
+}
+
+async fn process(
+    pool: &SqlitePool,
+    payment: &PaymentConfig,
+    provider: &str,
+    webhook_id: i64,
+    event: &WebhookEvent,
+) -> Result<WebhookOutcome> {
+    let now = Utc::now().naive_utc();
+    let claimed = sqlx::query!(
+        "INSERT OR IGNORE INTO t_payment_event (provider, event_id, webhook_id, created) VALUES (?1, ?2, ?3, ?4)",
+        provider,
+        event.event_id,
+        webhook_id,
+        now
+    ).execute(pool).await.context("记录推送事件失败")?;
+    if claimed.rows_affected() == 0 {
+        return Ok(WebhookOutcome::Duplicate);
+    }
+
+    // 未匹配或处理失败时释放事件，推送方重试时可再次处理（如订单稍后才进入可匹配状态）
+    let outcome = match payment_service::apply_transfer(pool, payment, &event.transfer).await {
+        Ok(Some(order_id)) => return Ok(WebhookOutcome::Processed(order_id)),
+        Ok(None) => WebhookOutcome::Unmatched,
+        Err(e) => {
+            warn!(provider, event_id = %event.event_id, error = %e, "payment webhook processing failed");
+            WebhookOutcome::Failed(e.to_string())
+        }
+    };
+    sqlx::query!(
+        "DELETE FROM t_payment_event WHERE provider = ?1 AND event_id = ?2",
+        provider,
+        event.event_id
+    ).execute(pool).await.context("释放推送事件失败")?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"whsec_test";
+    const BODY: &[u8] = br#"{"event_id":"evt_1","network":"usdt_trc20"}"#;
+
+    fn config() -> WebhookConfig {
+        WebhookConfig {
+            secrets: HashMap::from([("indexer".to_string(), SECRET.to_vec())]),
+            tolerance: Duration::from_secs(300),
+        }
+    }
+
+    fn sign(timestamp: i64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let now = Utc::now().timestamp();
+        let signature = sign(now, BODY);
+        assert!(verify(&config(), "indexer", Some(now), Some(&signature), BODY).is_ok());
+        // 允许 sha256= 前缀与首尾空白
+        let prefixed = format!(" sha256={} ", signature);
+        assert!(verify(&config(), "indexer", Some(now), Some(&prefixed), BODY).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_body_or_timestamp() {
+        let now = Utc::now().timestamp();
+        let signature = sign(now, BODY);
+        let tampered = br#"{"event_id":"evt_1","network":"usdt_erc20"}"#;
+        assert!(verify(&config(), "indexer", Some(now), Some(&signature), tampered).is_err());
+        // 签名绑定时间戳，换用其他时间戳重放同一签名无效
+        assert!(verify(&config(), "indexer", Some(now - 1), Some(&signature), BODY).is_err());
+    }
+
+    #[test]
+    fn rejects_stale_or_future_timestamp() {
+        let stale = Utc::now().timestamp() - 301;
+        assert!(verify(&config(), "indexer", Some(stale), Some(&sign(stale, BODY)), BODY).is_err());
+        let future = Utc::now().timestamp() + 301;
+        assert!(verify(&config(), "indexer", Some(future), Some(&sign(future, BODY)), BODY).is_err());
+        assert!(verify(&config(), "indexer", None, Some(&sign(0, BODY)), BODY).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_or_missing_signature() {
+        let now = Utc::now().timestamp();
+        for signature in ["", "sha256=", "not-hex", "sha256=zz", "deadbeef"] {
+            assert!(verify(&config(), "indexer", Some(now), Some(signature), BODY).is_err(), "{:?}", signature);
+        }
+        assert!(verify(&config(), "indexer", Some(now), None, BODY).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_provider() {
+        let now = Utc::now().timestamp();
+        assert!(verify(&config(), "other", Some(now), Some(&sign(now, BODY)), BODY).is_err());
+    }
+}
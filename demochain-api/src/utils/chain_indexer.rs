@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// 链上 USDT 转账记录（由索引服务返回）
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenTransfer {
+    pub network: String, // usdt_trc20 | usdt_erc20 | usdt_bep20
+    pub tx: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Decimal,          // 已按代币精度换算的金额
+    pub confirmations: u64,
+    pub timestamp: DateTime<Utc>, // 出块时间
+}
+
+/// 链上索引抽象：按网络和收款地址查询转入的 USDT 转账
+#[axum::async_trait]
+pub trait ChainIndexer: Send + Sync {
+    async fn transfers_to(&self, network: &str, address: &str) -> Result<Vec<TokenTransfer>>;
+}
+
+/// 离线测试用索引：每次查询时重新读取 JSON 文件（TokenTransfer 数组），
+/// 修改文件即可模拟到账和确认数增长；未配置文件时没有任何转账
+pub struct MockIndexer {
+    path: Option<PathBuf>,
+}
+
+impl MockIndexer {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+}
+
+#[axum::async_trait]
+impl ChainIndexer for MockIndexer {
+    async fn transfers_to(&self, network: &str, address: &str) -> Result<Vec<TokenTransfer>> {
+        let Some(path) = &self.path else {
+            return Ok(vec![]);
+        };
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        let transfers: Vec<TokenTransfer> = serde_json::from_str(&content)
+            .with_context(|| format!("invalid mock transfers in {}", path.display()))?;
+        Ok(transfers
+            .into_iter()
+            .filter(|t| t.network == network && t.to.eq_ignore_ascii_case(address))
+            .collect())
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use bip32::{ChildNumber, XPub};
+use sha3::{Digest, Keccak256};
+
+/// 解析扩展公钥（xpub），应为外部链节点，如 m/44'/60'/0'/0
+pub fn parse_xpub(s: &str) -> Result<XPub> {
+    s.trim().parse().map_err(|e| anyhow!("invalid xpub: {}", e))
+}
+
+/// 派生第 index 个非硬化子公钥，返回 keccak256(未压缩公钥) 的后 20 字节
+pub fn derive_account_hash(xpub: &XPub, index: u32) -> Result<[u8; 20]> {
+    let child_number = ChildNumber::new(index, false).map_err(|e| anyhow!("invalid index {}: {}", index, e))?;
+    let child = xpub.derive_child(child_number).map_err(|e| anyhow!("derive failed: {}", e))?;
+    let point = child.public_key().to_encoded_point(false);
+    let digest = Keccak256::digest(&point.as_bytes()[1..]);
+    let mut hash = [0u8; 20];
+    hash.copy_from_slice(&digest[12..]);
+    Ok(hash)
+}
+
+/// EVM 地址（EIP-55 大小写校验），用于 ERC20 / BEP20
+pub fn evm_address(hash: &[u8; 20]) -> String {
+    let lower = hex::encode(hash);
+    let checksum = Keccak256::digest(lower.as_bytes());
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (checksum[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        if c.is_ascii_alphabetic() && nibble >= 8 {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// TRON 地址：0x41 前缀 + 20 字节，base58check 编码
+pub fn tron_address(hash: &[u8; 20]) -> String {
+    let mut payload = Vec::with_capacity(21);
+    payload.push(0x41);
+    payload.extend_from_slice(hash);
+    bs58::encode(payload).with_check().into_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use bip32::{Prefix, XPrv};
+    use sha2::Sha512;
+
+    // BIP39 标准测试助记词
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    // 上述助记词在 m/44'/60'/0'/0 与 m/44'/195'/0'/0 的扩展公钥（部署时配置的形式）
+    pub(crate) const EVM_XPUB: &str = "xpub6EF8jXqFeFEW5bwMU7RpQtHkzE4KJxcqJtvkCjJumzW8CPpacXkb92ek4WzLQXjL93HycJwTPUAcuNxCqFPKKU5m5Z2Vq4nCyh5CyPeBFFr";
+    pub(crate) const TRON_XPUB: &str = "xpub6EuK4CZWW5urEHdwAVDdDw327danAtccFcrXYvgf1DHrPXRwErt36xStQ2PNhn4hpwzPbzJ8pJVpewgChRnSs59q5Ay61GCfQZKUe71gbLq";
+    // 两条链各自第 0 个地址
+    pub(crate) const EVM_ADDRESS_0: &str = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+    pub(crate) const TRON_ADDRESS_0: &str = "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH";
+
+    fn xpub_from_mnemonic(path: &str) -> String {
+        // BIP39 种子：PBKDF2-HMAC-SHA512(助记词, "mnemonic" + 口令, 2048)
+        let mut seed = [0u8; 64];
+        pbkdf2::pbkdf2_hmac::<Sha512>(MNEMONIC.as_bytes(), b"mnemonic", 2048, &mut seed);
+        let xprv = XPrv::derive_from_path(seed, &path.parse().unwrap()).unwrap();
+        xprv.public_key().to_string(Prefix::XPUB)
+    }
+
+    #[test]
+    fn xpubs_match_mnemonic() {
+        assert_eq!(xpub_from_mnemonic("m/44'/60'/0'/0"), EVM_XPUB);
+        assert_eq!(xpub_from_mnemonic("m/44'/195'/0'/0"), TRON_XPUB);
+    }
+
+    #[test]
+    fn evm_address_known_vector() {
+        let hash = derive_account_hash(&parse_xpub(EVM_XPUB).unwrap(), 0).unwrap();
+        assert_eq!(evm_address(&hash), EVM_ADDRESS_0);
+    }
+
+    #[test]
+    fn tron_address_known_vector() {
+        let hash = derive_account_hash(&parse_xpub(TRON_XPUB).unwrap(), 0).unwrap();
+        assert_eq!(tron_address(&hash), TRON_ADDRESS_0);
+    }
+
+    #[test]
+    fn derives_distinct_addresses_per_index() {
+        let xpub = parse_xpub(EVM_XPUB).unwrap();
+        let first = evm_address(&derive_account_hash(&xpub, 0).unwrap());
+        let second = evm_address(&derive_account_hash(&xpub, 1).unwrap());
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn rejects_hardened_index_and_bad_xpub() {
+        let xpub = parse_xpub(EVM_XPUB).unwrap();
+        assert!(derive_account_hash(&xpub, 1 << 31).is_err());
+        assert!(parse_xpub("xpub-not-a-key").is_err());
+    }
+}
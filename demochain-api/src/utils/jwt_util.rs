@@ -1,41 +1,155 @@
-use crate::models::user::Claims;
-use anyhow::{anyhow, Result};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use crate::models::user::{Claims, EmailVerifyClaims};
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use once_cell::sync::OnceCell;
 
-const JWT_SECRET: &str = "your-secret-key-change-in-production";
-const TOKEN_EXPIRY_SECONDS: i64 = 24 * 60 * 60; // 24 hours
+pub const DEFAULT_TOKEN_EXPIRY_SECONDS: i64 = 24 * 60 * 60; // 24 hours
+pub const DEFAULT_REFRESH_TOKEN_EXPIRY_SECONDS: i64 = 30 * 24 * 60 * 60; // 30 days
+pub const EMAIL_VERIFY_EXPIRY_SECONDS: i64 = 3 * 24 * 60 * 60; // 3 days
+
+static KEY_SET: OnceCell<JwtKeySet> = OnceCell::new();
+
+/// 单个签名/验签密钥，kid 写入 JWT header 用于轮换时定位密钥
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+}
+
+impl JwtKey {
+    /// HS256 共享密钥
+    pub fn from_secret(kid: String, secret: &[u8]) -> Self {
+        Self {
+            kid,
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(secret)),
+            decoding: DecodingKey::from_secret(secret),
+        }
+    }
+
+    /// RS256 / EdDSA 的 PEM 密钥；只用于验签的旧密钥不需要私钥
+    pub fn from_pem(kid: String, algorithm: Algorithm, private_pem: Option<&[u8]>, public_pem: &[u8]) -> Result<Self> {
+        let (encoding, decoding) = match algorithm {
+            Algorithm::RS256 => (
+                private_pem.map(EncodingKey::from_rsa_pem).transpose()?,
+                DecodingKey::from_rsa_pem(public_pem)?,
+            ),
+            Algorithm::EdDSA => (
+                private_pem.map(EncodingKey::from_ed_pem).transpose()?,
+                DecodingKey::from_ed_pem(public_pem)?,
+            ),
+            other => bail!("不支持的 PEM 签名算法: {:?}", other),
+        };
+        Ok(Self { kid, algorithm, encoding, decoding })
+    }
+}
+
+/// 当前生效的密钥集合：active 负责签发，其余密钥仅用于验签（轮换期间的旧密钥）
+pub struct JwtKeySet {
+    active: JwtKey,
+    retired: Vec<JwtKey>,
+    pub token_ttl: i64,
+    pub refresh_token_ttl: i64,
+}
+
+impl JwtKeySet {
+    pub fn new(active: JwtKey, retired: Vec<JwtKey>, token_ttl: i64, refresh_token_ttl: i64) -> Result<Self> {
+        if active.encoding.is_none() {
+            bail!("当前签名密钥 {} 缺少私钥", active.kid);
+        }
+        Ok(Self { active, retired, token_ttl, refresh_token_ttl })
+    }
+
+    /// 进程内随机密钥（未配置时的兜底，重启后所有令牌失效）
+    pub fn ephemeral() -> Self {
+        let secret = crate::utils::token::generate_opaque_token();
+        Self {
+            active: JwtKey::from_secret("ephemeral".to_string(), secret.as_bytes()),
+            retired: vec![],
+            token_ttl: DEFAULT_TOKEN_EXPIRY_SECONDS,
+            refresh_token_ttl: DEFAULT_REFRESH_TOKEN_EXPIRY_SECONDS,
+        }
+    }
+
+    fn keys(&self) -> impl Iterator<Item=&JwtKey> {
+        std::iter::once(&self.active).chain(self.retired.iter())
+    }
+}
 
 pub struct JwtService;
 
 impl JwtService {
-    pub fn generate_token(user_id: String, email: String) -> Result<String> {
-        let claims = Claims::new(user_id, email, TOKEN_EXPIRY_SECONDS);
-
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(JWT_SECRET.as_ref()),
-        )
-            .map_err(|e| anyhow!("Failed to generate token: {}", e))
+    /// 启动时安装密钥集合，只能调用一次
+    pub fn init(key_set: JwtKeySet) -> Result<()> {
+        KEY_SET.set(key_set).map_err(|_| anyhow!("JWT key set already initialized"))
+    }
+
+    fn key_set() -> &'static JwtKeySet {
+        KEY_SET.get_or_init(JwtKeySet::ephemeral)
+    }
+
+    pub fn token_ttl() -> i64 {
+        Self::key_set().token_ttl
+    }
+
+    pub fn refresh_token_ttl() -> i64 {
+        Self::key_set().refresh_token_ttl
+    }
+
+    pub fn generate_token(user_id: String, email: String, role: String) -> Result<String> {
+        Self::sign(&Claims::new(user_id, email, role, Self::token_ttl()))
     }
 
     pub fn verify_token(token: &str) -> Result<Claims> {
-        decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(JWT_SECRET.as_ref()),
-            &Validation::default(),
-        )
-            .map(|data| data.claims)
-            .map_err(|e| anyhow!("Invalid token: {}", e))
+        Self::verify(token)
+    }
+
+    /// 邮箱验证令牌，与访问令牌共用密钥，但字段不同，两者无法互相冒用
+    pub fn generate_email_token(user_id: String, email: String) -> Result<String> {
+        Self::sign(&EmailVerifyClaims::new(user_id, email, EMAIL_VERIFY_EXPIRY_SECONDS))
+    }
+
+    pub fn verify_email_token(token: &str) -> Result<EmailVerifyClaims> {
+        let claims: EmailVerifyClaims = Self::verify(token)?;
+        if claims.purpose != EmailVerifyClaims::PURPOSE {
+            bail!("Invalid token: unexpected purpose");
+        }
+        Ok(claims)
+    }
+
+    fn sign<T: Serialize>(claims: &T) -> Result<String> {
+        let active = &Self::key_set().active;
+        let mut header = Header::new(active.algorithm);
+        header.kid = Some(active.kid.clone());
+        let encoding = active.encoding.as_ref().ok_or_else(|| anyhow!("Missing signing key"))?;
+        encode(&header, claims, encoding)
+            .map_err(|e| anyhow!("Failed to generate token: {}", e))
+    }
+
+    fn verify<T: DeserializeOwned>(token: &str) -> Result<T> {
+        let header = decode_header(token).map_err(|e| anyhow!("Invalid token: {}", e))?;
+        // 有 kid 时按 kid 精确匹配；没有 kid 的旧令牌尝试同算法的全部密钥
+        let candidates = Self::key_set().keys().filter(|k| {
+            k.algorithm == header.alg && header.kid.as_ref().is_none_or(|kid| *kid == k.kid)
+        });
+        let mut last_err = anyhow!("Invalid token: unknown signing key");
+        for key in candidates {
+            match decode::<T>(token, &key.decoding, &Validation::new(key.algorithm)) {
+                Ok(data) => return Ok(data.claims),
+                Err(e) => last_err = anyhow!("Invalid token: {}", e),
+            }
+        }
+        Err(last_err)
     }
 
     #[allow(dead_code)]
     pub fn extract_bearer_token(auth_header: &str) -> Result<&str> {
-        if auth_header.starts_with("Bearer ") {
-            Ok(&auth_header[7..])
-        } else {
-            Err(anyhow!("Invalid authorization components format"))
-        }
+        auth_header
+            .strip_prefix("Bearer ")
+            .ok_or_else(|| anyhow!("Invalid authorization components format"))
     }
 }
 
@@ -50,7 +164,6 @@ use axum_extra::{
     TypedHeader,
 };
 
-#[allow(dead_code)]
 pub struct AuthUser(pub Claims);
 
 #[axum::async_trait]
@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Context, Result};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::PathBuf;
+use tracing::info;
+
+/// 待发送的邮件（纯文本）
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// 邮件发送抽象：生产环境走 SMTP，本地开发与测试写日志/文件
+#[axum::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<()>;
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, port: u16, username: String, password: String, from: &str) -> Result<Self> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+            .with_context(|| format!("invalid SMTP host {}", host))?
+            .port(port)
+            .credentials(Credentials::new(username, password))
+            .build();
+        let from = from.parse().map_err(|e| anyhow!("invalid MAIL_FROM {}: {}", from, e))?;
+        Ok(Self { transport, from })
+    }
+}
+
+#[axum::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        let to: Mailbox = mail.to.parse().map_err(|e| anyhow!("invalid recipient {}: {}", mail.to, e))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(mail.subject)
+            .body(mail.body)
+            .context("failed to build mail")?;
+        self.transport.send(message).await.context("failed to send mail")?;
+        Ok(())
+    }
+}
+
+/// 日志/文件投递：配置了目录时每封邮件写成一个文件，否则只打日志。
+/// 正文含重置密码、邮箱验证等令牌链接，日志中只记录收件人与主题
+pub struct LogMailer {
+    dir: Option<PathBuf>,
+}
+
+impl LogMailer {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+}
+
+#[axum::async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        match &self.dir {
+            Some(dir) => {
+                tokio::fs::create_dir_all(dir).await.context("failed to create mail dir")?;
+                let path = dir.join(format!(
+                    "{}-{}.eml",
+                    chrono::Utc::now().format("%Y%m%d%H%M%S"),
+                    uuid::Uuid::new_v4().simple()
+                ));
+                let content = format!("To: {}\nSubject: {}\n\n{}\n", mail.to, mail.subject, mail.body);
+                tokio::fs::write(&path, content).await.context("failed to write mail file")?;
+                info!(to = %mail.to, path = %path.display(), "mail written to file");
+            }
+            None => info!(to = %mail.to, subject = %mail.subject, "mail (log transport, body omitted; set MAIL_LOG_DIR to keep it)"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use lru::LruCache;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use sha2::{Digest, Sha256};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::models::article::{RenderedContent, TocEntry};
+
+// 渲染逻辑变化时递增，使旧缓存失效
+const RENDER_VERSION: &str = "2";
+// 代码高亮 class 前缀，与 highlight_css 输出的样式表对应
+const HIGHLIGHT_CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+// 标题锚点前缀；正文原始 HTML 中的 id 一律加上 RAW_ID_PREFIX，不会与标题锚点或页面上的其他 id 冲突
+const HEADING_ID_PREFIX: &str = "toc-";
+const RAW_ID_PREFIX: &str = "user-content-";
+// 阅读速度：中日韩文字按字计，其他按词计
+const CJK_CHARS_PER_MINUTE: usize = 300;
+const WORDS_PER_MINUTE: usize = 200;
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+// 生成的标题 id 在清洗前带上进程内随机标记，清洗时据此与原始 HTML 中的 id 区分
+fn heading_id_marker() -> &'static str {
+    static MARKER: OnceLock<String> = OnceLock::new();
+    MARKER.get_or_init(|| format!("m{}-", uuid::Uuid::new_v4().simple()))
+}
+
+fn sanitizer() -> &'static ammonia::Builder<'static> {
+    static SANITIZER: OnceLock<ammonia::Builder<'static>> = OnceLock::new();
+    SANITIZER.get_or_init(|| {
+        let mut builder = ammonia::Builder::default();
+        builder
+            .add_tag_attributes("span", &["class"])
+            .add_tag_attributes("code", &["class"])
+            .add_tag_attributes("pre", &["class"])
+            .attribute_filter(|_, attr, value| match attr {
+                "id" => Some(match value.strip_prefix(heading_id_marker()) {
+                    Some(id) => Cow::Owned(id.to_string()),
+                    None => Cow::Owned(format!("{}{}", RAW_ID_PREFIX, value)),
+                }),
+                _ => Some(Cow::Borrowed(value)),
+            });
+        for h in ["h1", "h2", "h3", "h4", "h5", "h6"] {
+            builder.add_tag_attributes(h, &["id"]);
+        }
+        builder
+    })
+}
+
+/// 代码高亮样式表（class 形式，前端按需引入）
+pub fn highlight_css() -> &'static str {
+    static CSS: OnceLock<String> = OnceLock::new();
+    CSS.get_or_init(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .get(HIGHLIGHT_THEME)
+            .and_then(|theme| css_for_theme_with_class_style(theme, HIGHLIGHT_CLASS_STYLE).ok())
+            .unwrap_or_default()
+    })
+}
+
+/// Markdown 渲染为清洗后的 HTML，同时生成目录与阅读时长。
+/// 正文中的原始 HTML 会经过白名单清洗，脚本、事件属性与危险链接全部移除
+pub fn render(markdown: &str) -> RenderedContent {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES;
+    let mut events: Vec<Event> = Vec::new();
+    let mut toc: Vec<TocEntry> = Vec::new();
+    let mut used_ids: HashSet<String> = HashSet::new();
+    let mut plain = String::new();
+
+    let mut parser = Parser::new_ext(markdown, options);
+    while let Some(event) = parser.next() {
+        match event {
+            Event::Start(Tag::Heading { level, classes, attrs, .. }) => {
+                let mut inner = Vec::new();
+                let mut text = String::new();
+                for e in parser.by_ref() {
+                    match e {
+                        Event::End(TagEnd::Heading(_)) => break,
+                        Event::Text(ref t) | Event::Code(ref t) => text.push_str(t),
+                        _ => {}
+                    }
+                    inner.push(e);
+                }
+                let id = heading_id(&text, toc.len() + 1, &mut used_ids);
+                plain.push_str(&text);
+                plain.push('\n');
+                toc.push(TocEntry { level: level as u8, text: text.trim().to_string(), id: id.clone() });
+                let marked = format!("{}{}", heading_id_marker(), id);
+                events.push(Event::Start(Tag::Heading { level, id: Some(CowStr::from(marked)), classes, attrs }));
+                events.extend(inner);
+                events.push(Event::End(TagEnd::Heading(level)));
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let mut code = String::new();
+                for e in parser.by_ref() {
+                    match e {
+                        Event::End(TagEnd::CodeBlock) => break,
+                        Event::Text(t) => code.push_str(&t),
+                        _ => {}
+                    }
+                }
+                let lang = match &kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or_default().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                plain.push_str(&code);
+                events.push(Event::Html(CowStr::from(highlight_code(&code, &lang))));
+            }
+            Event::Text(ref t) | Event::Code(ref t) => {
+                plain.push_str(t);
+                plain.push(' ');
+                events.push(event);
+            }
+            e => events.push(e),
+        }
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    RenderedContent {
+        html: sanitizer().clean(&html).to_string(),
+        toc,
+        reading_minutes: reading_minutes(&plain),
+    }
+}
+
+// 标题锚点：拼音/字母 slug，无法生成时按序号；重复时追加 -2、-3…
+fn heading_id(text: &str, index: usize, used: &mut HashSet<String>) -> String {
+    let base = match slug::slugify(text) {
+        s if s.is_empty() => format!("{}section-{}", HEADING_ID_PREFIX, index),
+        s => format!("{}{}", HEADING_ID_PREFIX, s),
+    };
+    let mut id = base.clone();
+    let mut n = 1;
+    while !used.insert(id.clone()) {
+        n += 1;
+        id = format!("{}-{}", base, n);
+    }
+    id
+}
+
+fn highlight_code(code: &str, lang: &str) -> String {
+    let syntaxes = syntax_set();
+    let syntax = (!lang.is_empty())
+        .then(|| syntaxes.find_syntax_by_token(lang))
+        .flatten();
+    let body = match syntax {
+        Some(syntax) => {
+            let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, HIGHLIGHT_CLASS_STYLE);
+            let highlighted = LinesWithEndings::from(code)
+                .try_for_each(|line| generator.parse_html_for_line_which_includes_newline(line));
+            match highlighted {
+                Ok(()) => generator.finalize(),
+                Err(_) => escape_html(code),
+            }
+        }
+        None => escape_html(code),
+    };
+    let lang_class = lang.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+')).collect::<String>();
+    if lang_class.is_empty() {
+        format!("<pre class=\"hl-code\"><code>{}</code></pre>\n", body)
+    } else {
+        format!("<pre class=\"hl-code\"><code class=\"language-{}\">{}</code></pre>\n", lang_class, body)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'   // 平假名、片假名
+        | '\u{3400}'..='\u{4dbf}' // 扩展 A
+        | '\u{4e00}'..='\u{9fff}' // 基本汉字
+        | '\u{ac00}'..='\u{d7af}' // 韩文
+        | '\u{f900}'..='\u{faff}' // 兼容汉字
+    )
+}
+
+fn reading_minutes(plain: &str) -> u32 {
+    let cjk = plain.chars().filter(|c| is_cjk(*c)).count();
+    let words = plain
+        .split(|c: char| c.is_whitespace() || is_cjk(c))
+        .filter(|w| w.chars().any(|c| c.is_alphanumeric()))
+        .count();
+    let minutes = (cjk as f64 / CJK_CHARS_PER_MINUTE as f64 + words as f64 / WORDS_PER_MINUTE as f64).ceil();
+    minutes.max(1.0) as u32
+}
+
+/// 渲染结果缓存：按正文内容哈希（含渲染版本）索引，正文不变时直接复用
+pub struct RenderCache {
+    entries: Mutex<LruCache<[u8; 32], Arc<RenderedContent>>>,
+}
+
+impl RenderCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { entries: Mutex::new(LruCache::new(capacity)) }
+    }
+
+    pub async fn render(&self, markdown: String) -> anyhow::Result<Arc<RenderedContent>> {
+        let mut hasher = Sha256::new();
+        hasher.update(RENDER_VERSION.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(markdown.as_bytes());
+        let key: [u8; 32] = hasher.finalize().into();
+
+        if let Some(hit) = self.entries.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(hit.clone());
+        }
+        // 渲染与高亮是 CPU 密集操作，放到阻塞线程池执行
+        let rendered = Arc::new(tokio::task::spawn_blocking(move || render(&markdown)).await?);
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).put(key, rendered.clone());
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_scripts_event_handlers_and_js_links() {
+        let html = render(concat!(
+            "<script>alert(1)</script>\n\n",
+            "<img src=\"x.png\" onerror=\"alert(1)\">\n\n",
+            "<a href=\"javascript:alert(1)\" onclick=\"alert(1)\">link</a>\n\n",
+            "<p onmouseover=\"alert(1)\">hover</p>\n",
+        )).html;
+        for banned in ["<script", "alert(1)", "onerror", "onclick", "onmouseover", "javascript:"] {
+            assert!(!html.contains(banned), "{} in {}", banned, html);
+        }
+        assert!(html.contains("<img src=\"x.png\""));
+        assert!(html.contains(">link</a>"));
+    }
+
+    #[test]
+    fn prefixes_raw_html_ids() {
+        let html = render("<h2 id=\"install\">Raw</h2>\n").html;
+        assert!(html.contains("id=\"user-content-install\""), "{}", html);
+    }
+
+    #[test]
+    fn raw_ids_cannot_claim_toc_anchors() {
+        let rendered = render("# Intro\n\n<h2 id=\"toc-intro\">Fake</h2>\n");
+        let ids: Vec<&str> = rendered.toc.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["toc-intro"]);
+        assert_eq!(rendered.html.matches("id=\"toc-intro\"").count(), 1);
+        assert!(rendered.html.contains("id=\"user-content-toc-intro\""));
+    }
+
+    #[test]
+    fn heading_ids_drop_marker_and_match_toc() {
+        let rendered = render("# Intro\n\n## Intro\n\n### !!!\n");
+        let ids: Vec<&str> = rendered.toc.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["toc-intro", "toc-intro-2", "toc-section-3"]);
+        for id in ids {
+            assert!(rendered.html.contains(&format!("id=\"{}\"", id)), "{}", rendered.html);
+        }
+        assert!(!rendered.html.contains(heading_id_marker()));
+    }
+}
@@ -1,4 +1,12 @@
 pub mod time;
 pub mod jwt_util;
 pub mod password;
+pub mod token;
+pub mod mailer;
+pub mod plan_util;
+pub mod chain_indexer;
+pub mod hd_wallet;
+pub mod money;
 
+pub mod role_util;
+pub mod markdown;
@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// 解析数据库中以十进制字符串保存的金额
+pub fn parse(s: &str) -> anyhow::Result<Decimal> {
+    Decimal::from_str(s.trim()).with_context(|| format!("金额格式无效: {}", s))
+}
+
+/// 各网络 USDT 合约的精度
+pub fn decimals(network: &str) -> u32 {
+    match network {
+        "usdt_bep20" => 18,
+        _ => 6,
+    }
+}
+
+/// 按网络精度四舍五入，保证金额可在链上精确支付
+pub fn round_for_network(amount: Decimal, network: &str) -> Decimal {
+    amount
+        .round_dp_with_strategy(decimals(network), RoundingStrategy::MidpointAwayFromZero)
+        .normalize()
+}
+
+/// 金额换算为链上最小单位（整数字符串）
+pub fn to_base_units(amount: Decimal, network: &str) -> Option<String> {
+    let scale = Decimal::from_i128_with_scale(10i128.checked_pow(decimals(network))?, 0);
+    let units = amount.checked_mul(scale)?;
+    units.fract().is_zero().then(|| units.trunc().normalize().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn parses_decimal_strings() {
+        assert_eq!(parse("9.99").unwrap(), dec("9.99"));
+        assert_eq!(parse(" 12.000001 ").unwrap(), dec("12.000001"));
+        assert_eq!(parse("0.000000000000000001").unwrap(), dec("0.000000000000000001"));
+        assert!(parse("").is_err());
+        assert!(parse("1,5").is_err());
+        assert!(parse("abc").is_err());
+    }
+
+    #[test]
+    fn network_decimals() {
+        assert_eq!(decimals("usdt_bep20"), 18);
+        assert_eq!(decimals("usdt_erc20"), 6);
+        assert_eq!(decimals("usdt_trc20"), 6);
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero_at_network_precision() {
+        assert_eq!(round_for_network(dec("1.0000005"), "usdt_trc20"), dec("1.000001"));
+        assert_eq!(round_for_network(dec("1.0000004"), "usdt_erc20"), dec("1"));
+        assert_eq!(round_for_network(dec("-1.0000005"), "usdt_trc20"), dec("-1.000001"));
+        // BEP20 保留 18 位
+        assert_eq!(round_for_network(dec("1.0000005"), "usdt_bep20"), dec("1.0000005"));
+        assert_eq!(
+            round_for_network(dec("0.0000000000000000015"), "usdt_bep20"),
+            dec("0.000000000000000002")
+        );
+        // 结果去除多余的尾随零
+        assert_eq!(round_for_network(dec("5.100000"), "usdt_trc20").to_string(), "5.1");
+    }
+
+    #[test]
+    fn converts_to_base_units() {
+        assert_eq!(to_base_units(dec("9.99"), "usdt_trc20").as_deref(), Some("9990000"));
+        assert_eq!(to_base_units(dec("10"), "usdt_erc20").as_deref(), Some("10000000"));
+        assert_eq!(to_base_units(dec("0.000001"), "usdt_trc20").as_deref(), Some("1"));
+        assert_eq!(to_base_units(dec("9.99"), "usdt_bep20").as_deref(), Some("9990000000000000000"));
+        // 超出网络精度的金额无法精确支付
+        assert_eq!(to_base_units(dec("1.0000001"), "usdt_trc20"), None);
+    }
+
+    #[test]
+    fn base_units_overflow_is_none() {
+        // 18 位精度下超过 Decimal 上限（约 7.9e28）
+        assert_eq!(to_base_units(dec("100000000000"), "usdt_bep20"), None);
+        assert_eq!(to_base_units(dec("10000000000"), "usdt_bep20").as_deref(), Some("10000000000000000000000000000"));
+        assert_eq!(to_base_units(Decimal::MAX, "usdt_trc20"), None);
+    }
+}
@@ -0,0 +1,83 @@
+use std::marker::PhantomData;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tracing::error;
+
+use crate::app::AppState;
+use crate::models::subscription::PlanType;
+use crate::models::user::Claims;
+use crate::models::R;
+use crate::service::subscription_service;
+use crate::utils::jwt_util::AuthUser;
+
+/// 套餐门槛（类型级标记，供 RequirePlan 使用）；需要更高等级时新增标记类型即可
+pub trait PlanRequirement: Send + Sync {
+    const MIN: PlanType;
+}
+
+/// 任意付费套餐
+pub struct Paid;
+
+impl PlanRequirement for Paid {
+    const MIN: PlanType = PlanType::Monthly;
+}
+
+/// 付费接口提取器：未登录返回 401，免费用户返回 402，套餐等级不足返回 403
+pub struct RequirePlan<P: PlanRequirement = Paid> {
+    pub claims: Claims,
+    _plan: PhantomData<P>,
+}
+
+pub struct PlanRejection {
+    status: StatusCode,
+    message: String,
+}
+
+impl IntoResponse for PlanRejection {
+    fn into_response(self) -> Response {
+        let body: R<()> = R {
+            success: false,
+            data: None,
+            message: Some(self.message),
+            code: Some(self.status.as_u16() as i32),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[axum::async_trait]
+impl<P: PlanRequirement> FromRequestParts<AppState> for RequirePlan<P> {
+    type Rejection = PlanRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let AuthUser(claims) = AuthUser::from_request_parts(parts, state)
+            .await
+            .map_err(|status| PlanRejection { status, message: "请先登录".to_string() })?;
+
+        let entitlement = subscription_service::current(&state.db, &claims.sub)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "failed to load entitlement");
+                PlanRejection {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    message: "查询套餐失败".to_string(),
+                }
+            })?;
+
+        match entitlement {
+            None => Err(PlanRejection {
+                status: StatusCode::PAYMENT_REQUIRED,
+                message: "当前 Free 计划不支持此功能".to_string(),
+            }),
+            Some(e) if e.plan_type < P::MIN => Err(PlanRejection {
+                status: StatusCode::FORBIDDEN,
+                message: format!("此功能需要 {} 及以上套餐", P::MIN.as_str()),
+            }),
+            Some(_) => Ok(RequirePlan { claims, _plan: PhantomData }),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::marker::PhantomData;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tracing::error;
+
+use crate::app::AppState;
+use crate::models::user::{Claims, ROLE_ADMIN, ROLE_EDITOR};
+use crate::models::R;
+use crate::service::token_service;
+use crate::utils::jwt_util::AuthUser;
+
+/// 角色门槛（类型级标记，供 RequireRole 使用）
+pub trait RoleRequirement: Send + Sync {
+    const ROLES: &'static [&'static str];
+}
+
+/// 管理员
+pub struct Admin;
+
+impl RoleRequirement for Admin {
+    const ROLES: &'static [&'static str] = &[ROLE_ADMIN];
+}
+
+/// 文章编辑（管理员同样可以编辑）
+pub struct Editor;
+
+impl RoleRequirement for Editor {
+    const ROLES: &'static [&'static str] = &[ROLE_EDITOR, ROLE_ADMIN];
+}
+
+/// 角色接口提取器：未登录返回 401，角色不符返回 403；
+/// 角色以数据库为准，令牌签发后被降级的账号立即失去权限。
+/// 公开路径（如 /api/article/）不经过鉴权中间件，因此这里自行检查令牌是否已吊销
+pub struct RequireRole<R: RoleRequirement = Admin> {
+    pub claims: Claims,
+    _role: PhantomData<R>,
+}
+
+pub struct RoleRejection {
+    status: StatusCode,
+    message: String,
+}
+
+impl IntoResponse for RoleRejection {
+    fn into_response(self) -> Response {
+        let body: R<()> = R {
+            success: false,
+            data: None,
+            message: Some(self.message),
+            code: Some(self.status.as_u16() as i32),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[axum::async_trait]
+impl<R: RoleRequirement> FromRequestParts<AppState> for RequireRole<R> {
+    type Rejection = RoleRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let AuthUser(claims) = AuthUser::from_request_parts(parts, state)
+            .await
+            .map_err(|status| RoleRejection { status, message: "请先登录".to_string() })?;
+
+        let revoked = token_service::is_token_revoked(&state.db, &claims)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "failed to check token denylist");
+                RoleRejection {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    message: "查询用户失败".to_string(),
+                }
+            })?;
+        if revoked {
+            return Err(RoleRejection { status: StatusCode::UNAUTHORIZED, message: "请先登录".to_string() });
+        }
+
+        let forbidden = || RoleRejection { status: StatusCode::FORBIDDEN, message: "没有权限".to_string() };
+        if !R::ROLES.contains(&claims.role.as_str()) {
+            return Err(forbidden());
+        }
+
+        let user = sqlx::query!(
+            r#"SELECT role FROM t_user WHERE id = ?1 AND disabled_at IS NULL"#,
+            claims.sub
+        )
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "failed to load user role");
+                RoleRejection {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    message: "查询用户失败".to_string(),
+                }
+            })?;
+
+        match user {
+            Some(u) if R::ROLES.contains(&u.role.as_str()) => Ok(RequireRole { claims, _role: PhantomData }),
+            _ => Err(forbidden()),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// 生成不透明的随机令牌（刷新令牌等，约 244 bit 随机性）
+pub fn generate_opaque_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// 令牌入库前取 SHA-256，数据库中只保存哈希
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
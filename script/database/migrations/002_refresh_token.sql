@@ -0,0 +1,12 @@
+-- 刷新令牌表（只保存哈希，family_id 标识同一次登录派生出的令牌链）
+CREATE TABLE t_refresh_token (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,                -- 自增主键
+    user_id TEXT NOT NULL,
+    family_id TEXT NOT NULL,                             -- 令牌家族，轮换时保持不变
+    token_hash TEXT UNIQUE NOT NULL,                     -- SHA-256(token)
+    revoked INTEGER NOT NULL DEFAULT 0,                  -- 1 = 已轮换或已吊销
+    expires DATETIME NOT NULL,                           -- 过期时间
+    created DATETIME NOT NULL DEFAULT (datetime('now'))  -- 创建时间（自动）
+);
+
+CREATE INDEX idx_refresh_token_family ON t_refresh_token (family_id);
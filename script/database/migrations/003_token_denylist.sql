@@ -0,0 +1,9 @@
+-- 访问令牌吊销表（登出后 jti 入表，过期后由后台任务清理）
+CREATE TABLE t_token_denylist (
+    jti TEXT PRIMARY KEY,                                -- JWT ID
+    user_id TEXT NOT NULL,
+    expires DATETIME NOT NULL,                           -- 令牌原过期时间
+    created DATETIME NOT NULL DEFAULT (datetime('now'))  -- 创建时间（自动）
+);
+
+CREATE INDEX idx_token_denylist_expires ON t_token_denylist (expires);
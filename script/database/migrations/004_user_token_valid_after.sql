@@ -0,0 +1,2 @@
+-- 修改密码后，签发时间（iat）早于该时间戳的令牌全部失效
+ALTER TABLE t_user ADD COLUMN token_valid_after INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,11 @@
+-- 密码重置令牌（只保存哈希，一次性使用，过期失效）
+CREATE TABLE t_password_reset (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,                -- 自增主键
+    user_id TEXT NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL,                     -- SHA-256(token)
+    expires DATETIME NOT NULL,                           -- 过期时间
+    used DATETIME,                                       -- 使用时间，NULL 表示未使用
+    created DATETIME NOT NULL DEFAULT (datetime('now'))  -- 创建时间（自动）
+);
+
+CREATE INDEX idx_password_reset_user ON t_password_reset (user_id);
@@ -0,0 +1,2 @@
+-- 邮箱验证时间，NULL 表示未验证
+ALTER TABLE t_user ADD COLUMN verified_at DATETIME;
@@ -0,0 +1,12 @@
+-- 订阅/权益表：订单确认后写入，用户 vip 等级由此表推导
+CREATE TABLE t_subscription (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,                -- 自增主键
+    user_id TEXT NOT NULL,
+    order_id INTEGER UNIQUE NOT NULL,                    -- 来源订单，一个订单只发放一次
+    plan_type TEXT NOT NULL,                             -- monthly | yearly | lifetime
+    starts DATETIME NOT NULL,                            -- 生效时间（续费时顺延到上一期结束）
+    expires DATETIME,                                    -- 到期时间，NULL 表示永久
+    created DATETIME NOT NULL DEFAULT (datetime('now'))  -- 创建时间（自动）
+);
+
+CREATE INDEX idx_subscription_user ON t_subscription (user_id);
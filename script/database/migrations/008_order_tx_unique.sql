@@ -0,0 +1,2 @@
+-- 同一笔链上交易只能匹配一个订单
+CREATE UNIQUE INDEX idx_order_tx ON t_order (tx) WHERE tx IS NOT NULL;
@@ -0,0 +1,10 @@
+-- HD 派生索引计数器（evm: ERC20/BEP20 共用，tron: TRC20）
+CREATE TABLE t_hd_index (
+    chain TEXT PRIMARY KEY,                              -- evm | tron
+    next_index INTEGER NOT NULL DEFAULT 0                -- 下一个可用的派生索引
+);
+
+INSERT INTO t_hd_index (chain, next_index) VALUES ('evm', 0), ('tron', 0);
+
+-- 订单收款地址对应的派生索引，NULL 表示使用固定收款地址
+ALTER TABLE t_order ADD COLUMN derivation_index INTEGER;
@@ -0,0 +1,19 @@
+-- 订单过期时间：created 状态超过该时间未支付将被置为 expired
+ALTER TABLE t_order ADD COLUMN expires DATETIME;
+
+UPDATE t_order SET expires = datetime(created, '+60 minutes') WHERE expires IS NULL;
+
+CREATE INDEX idx_order_state_expires ON t_order(state, expires);
+
+-- 订单状态变更审计
+CREATE TABLE t_order_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL,                           -- 订单ID
+    from_state TEXT NOT NULL,                            -- 变更前状态
+    to_state TEXT NOT NULL,                              -- 变更后状态
+    actor TEXT NOT NULL,                                 -- 操作方：用户ID | system | payment
+    reason TEXT,                                         -- 变更原因
+    created DATETIME NOT NULL                            -- 变更时间
+);
+
+CREATE INDEX idx_order_history_order_id ON t_order_history(order_id);
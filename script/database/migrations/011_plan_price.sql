@@ -0,0 +1,24 @@
+-- 套餐价格目录：按套餐 + 网络定价，同一组合可预置多条生效区间
+CREATE TABLE t_plan_price (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_type TEXT NOT NULL,                             -- monthly | yearly | lifetime
+    network TEXT NOT NULL,                               -- usdt_trc20 | usdt_erc20 | usdt_bep20
+    currency TEXT NOT NULL,                              -- 计价币种，如 USDT
+    amount REAL NOT NULL,                                -- 价格
+    enabled INTEGER NOT NULL DEFAULT 1,                  -- 是否上架
+    effective_from DATETIME NOT NULL,                    -- 生效时间
+    effective_to DATETIME,                               -- 失效时间，NULL 表示长期有效
+    created DATETIME NOT NULL
+);
+
+CREATE INDEX idx_plan_price_lookup ON t_plan_price(plan_type, network, effective_from);
+
+-- 初始价格与原硬编码价格一致
+INSERT INTO t_plan_price (plan_type, network, currency, amount, enabled, effective_from, effective_to, created)
+SELECT p.plan_type, n.network, 'USDT', p.amount, 1, '2000-01-01 00:00:00', NULL, datetime('now')
+FROM (SELECT 'monthly' AS plan_type, 3.0 AS amount
+      UNION ALL SELECT 'yearly', 10.0
+      UNION ALL SELECT 'lifetime', 15.0) p
+CROSS JOIN (SELECT 'usdt_trc20' AS network
+            UNION ALL SELECT 'usdt_erc20'
+            UNION ALL SELECT 'usdt_bep20') n;
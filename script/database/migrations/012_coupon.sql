@@ -0,0 +1,31 @@
+-- 优惠码
+CREATE TABLE t_coupon (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE COLLATE NOCASE,            -- 优惠码（不区分大小写）
+    discount_type TEXT NOT NULL,                         -- percent | fixed
+    discount_value REAL NOT NULL,                        -- percent: 折扣百分比（如 20 表示减 20%）；fixed: 减免金额
+    max_redemptions INTEGER,                             -- 总使用上限，NULL 表示不限
+    redeemed_count INTEGER NOT NULL DEFAULT 0,           -- 已使用次数
+    per_user_limit INTEGER,                              -- 每个用户使用上限，NULL 表示不限
+    plan_types TEXT,                                     -- 适用套餐，逗号分隔，NULL 表示全部
+    valid_from DATETIME,                                 -- 生效时间
+    valid_to DATETIME,                                   -- 失效时间
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created DATETIME NOT NULL
+);
+
+-- 优惠码使用记录：订单取消或过期时删除并归还次数
+CREATE TABLE t_coupon_redemption (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    coupon_id INTEGER NOT NULL,
+    user_id TEXT NOT NULL,
+    order_id INTEGER NOT NULL UNIQUE,
+    discount REAL NOT NULL,                              -- 实际减免金额
+    created DATETIME NOT NULL
+);
+
+CREATE INDEX idx_coupon_redemption_coupon_user ON t_coupon_redemption(coupon_id, user_id);
+
+-- 订单使用的优惠码与减免金额
+ALTER TABLE t_order ADD COLUMN coupon_code TEXT;
+ALTER TABLE t_order ADD COLUMN discount REAL;
@@ -0,0 +1,98 @@
+-- 金额字段由 REAL 改为十进制字符串（TEXT），避免浮点误差影响链上金额匹配。
+-- SQLite 不支持修改列类型，按 新建 -> 复制 -> 删除 -> 重命名 的方式重建表。
+
+-- t_order
+CREATE TABLE t_order_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL,
+    plan_type TEXT NOT NULL,
+    amount TEXT NOT NULL,                                -- 十进制字符串，如 "9.99"
+    currency TEXT NOT NULL,
+    network TEXT NOT NULL,
+    state TEXT NOT NULL,                                 -- created | paid | confirmed | expired | cancelled
+    address TEXT NOT NULL,
+    sender_address TEXT,
+    tx TEXT,
+    created DATETIME NOT NULL DEFAULT (datetime('now')),
+    updated DATETIME NOT NULL DEFAULT (datetime('now')),
+    derivation_index INTEGER,
+    expires DATETIME,
+    coupon_code TEXT,
+    discount TEXT                                        -- 十进制字符串
+);
+
+INSERT INTO t_order_new
+SELECT id, user_id, plan_type, CAST(amount AS TEXT), currency, network, state, address, sender_address, tx,
+       created, updated, derivation_index, expires, coupon_code, CAST(discount AS TEXT)
+FROM t_order;
+
+DROP TABLE t_order;
+ALTER TABLE t_order_new RENAME TO t_order;
+
+CREATE UNIQUE INDEX idx_order_tx ON t_order (tx) WHERE tx IS NOT NULL;
+CREATE INDEX idx_order_state_expires ON t_order(state, expires);
+
+-- t_plan_price
+CREATE TABLE t_plan_price_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    plan_type TEXT NOT NULL,
+    network TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    amount TEXT NOT NULL,                                -- 十进制字符串
+    enabled INTEGER NOT NULL DEFAULT 1,
+    effective_from DATETIME NOT NULL,
+    effective_to DATETIME,
+    created DATETIME NOT NULL
+);
+
+INSERT INTO t_plan_price_new
+SELECT id, plan_type, network, currency, CAST(amount AS TEXT), enabled, effective_from, effective_to, created
+FROM t_plan_price;
+
+DROP TABLE t_plan_price;
+ALTER TABLE t_plan_price_new RENAME TO t_plan_price;
+
+CREATE INDEX idx_plan_price_lookup ON t_plan_price(plan_type, network, effective_from);
+
+-- t_coupon
+CREATE TABLE t_coupon_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    discount_type TEXT NOT NULL,                         -- percent | fixed
+    discount_value TEXT NOT NULL,                        -- 十进制字符串
+    max_redemptions INTEGER,
+    redeemed_count INTEGER NOT NULL DEFAULT 0,
+    per_user_limit INTEGER,
+    plan_types TEXT,
+    valid_from DATETIME,
+    valid_to DATETIME,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created DATETIME NOT NULL
+);
+
+INSERT INTO t_coupon_new
+SELECT id, code, discount_type, CAST(discount_value AS TEXT), max_redemptions, redeemed_count, per_user_limit,
+       plan_types, valid_from, valid_to, enabled, created
+FROM t_coupon;
+
+DROP TABLE t_coupon;
+ALTER TABLE t_coupon_new RENAME TO t_coupon;
+
+-- t_coupon_redemption
+CREATE TABLE t_coupon_redemption_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    coupon_id INTEGER NOT NULL,
+    user_id TEXT NOT NULL,
+    order_id INTEGER NOT NULL UNIQUE,
+    discount TEXT NOT NULL,                              -- 十进制字符串
+    created DATETIME NOT NULL
+);
+
+INSERT INTO t_coupon_redemption_new
+SELECT id, coupon_id, user_id, order_id, CAST(discount AS TEXT), created
+FROM t_coupon_redemption;
+
+DROP TABLE t_coupon_redemption;
+ALTER TABLE t_coupon_redemption_new RENAME TO t_coupon_redemption;
+
+CREATE INDEX idx_coupon_redemption_coupon_user ON t_coupon_redemption(coupon_id, user_id);
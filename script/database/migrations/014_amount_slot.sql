@@ -0,0 +1,6 @@
+-- 唯一金额归属：共享收款地址时给应付金额附加的尾差序号，NULL 表示未附加
+ALTER TABLE t_order ADD COLUMN amount_slot INTEGER;
+
+-- 同一网络同一地址上的待支付订单金额不能重复；订单过期/取消/确认后自动释放
+CREATE UNIQUE INDEX idx_order_open_amount ON t_order (network, address, amount)
+    WHERE amount_slot IS NOT NULL AND state IN ('created', 'paid');
@@ -0,0 +1,24 @@
+-- 支付 webhook 原始请求（含验签失败的请求），用于审计
+CREATE TABLE t_payment_webhook (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider TEXT NOT NULL,                              -- 推送方标识（路由参数）
+    event_id TEXT,                                       -- 推送方事件ID（验签通过并解析成功后记录）
+    signature TEXT,                                      -- 请求头中的签名
+    timestamp INTEGER,                                   -- 请求头中的时间戳（秒）
+    payload TEXT NOT NULL,                               -- 原始请求体
+    status TEXT NOT NULL,                                -- received | rejected | invalid | duplicate | unmatched | processed | failed
+    message TEXT,                                        -- 处理说明或错误信息
+    order_id INTEGER,                                    -- 被推进的订单
+    received DATETIME NOT NULL
+);
+
+CREATE INDEX idx_payment_webhook_event ON t_payment_webhook(provider, event_id);
+
+-- 已处理的推送事件，用于幂等
+CREATE TABLE t_payment_event (
+    provider TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    webhook_id INTEGER NOT NULL,                         -- 首次处理该事件的请求
+    created DATETIME NOT NULL,
+    PRIMARY KEY (provider, event_id)
+);
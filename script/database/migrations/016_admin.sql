@@ -0,0 +1,9 @@
+-- 用户角色：user | admin（管理员通过 SQL 指定，如 UPDATE t_user SET role = 'admin' WHERE email = ...）
+ALTER TABLE t_user ADD COLUMN role TEXT NOT NULL DEFAULT 'user';
+
+-- 账号禁用
+ALTER TABLE t_user ADD COLUMN disabled_at DATETIME;
+ALTER TABLE t_user ADD COLUMN disabled_reason TEXT;
+
+-- 订单退款时撤销对应权益
+ALTER TABLE t_subscription ADD COLUMN revoked_at DATETIME;
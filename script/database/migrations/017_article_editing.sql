@@ -0,0 +1,24 @@
+-- 文章编辑：更新时间、软删除、作者
+ALTER TABLE t_article ADD COLUMN updated DATETIME;
+ALTER TABLE t_article ADD COLUMN deleted_at DATETIME;
+ALTER TABLE t_article ADD COLUMN author_id TEXT;
+
+UPDATE t_article SET updated = created WHERE updated IS NULL;
+
+-- 文章版本：每次创建、修改、恢复都保存一份完整快照
+CREATE TABLE t_article_revision (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_id INTEGER NOT NULL,                         -- 文章ID
+    title TEXT NOT NULL,
+    excerpt TEXT,
+    content TEXT,
+    tags TEXT,                                           -- JSON 数组
+    editor_id TEXT NOT NULL,                             -- 编辑者用户ID
+    created DATETIME NOT NULL                            -- 保存时间
+);
+
+CREATE INDEX idx_article_revision_article_id ON t_article_revision(article_id);
+
+-- 把现有文章作为第一版
+INSERT INTO t_article_revision (article_id, title, excerpt, content, tags, editor_id, created)
+SELECT id, title, excerpt, content, tags, 'system', created FROM t_article;
@@ -0,0 +1,31 @@
+-- 文章全文检索：trigram 分词按三字滑窗建索引，中文无需分词词典即可做子串匹配
+-- （检索词不足三个字时由服务端回退到子串扫描）
+CREATE VIRTUAL TABLE t_article_fts USING fts5(
+    title,
+    excerpt,
+    content,
+    tags,
+    content = 't_article',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+INSERT INTO t_article_fts(t_article_fts) VALUES ('rebuild');
+
+-- 与 t_article 保持同步（外部内容表删除时需带上旧值）
+CREATE TRIGGER trg_article_fts_insert AFTER INSERT ON t_article BEGIN
+    INSERT INTO t_article_fts (rowid, title, excerpt, content, tags)
+    VALUES (new.id, new.title, new.excerpt, new.content, new.tags);
+END;
+
+CREATE TRIGGER trg_article_fts_delete AFTER DELETE ON t_article BEGIN
+    INSERT INTO t_article_fts (t_article_fts, rowid, title, excerpt, content, tags)
+    VALUES ('delete', old.id, old.title, old.excerpt, old.content, old.tags);
+END;
+
+CREATE TRIGGER trg_article_fts_update AFTER UPDATE OF title, excerpt, content, tags ON t_article BEGIN
+    INSERT INTO t_article_fts (t_article_fts, rowid, title, excerpt, content, tags)
+    VALUES ('delete', old.id, old.title, old.excerpt, old.content, old.tags);
+    INSERT INTO t_article_fts (rowid, title, excerpt, content, tags)
+    VALUES (new.id, new.title, new.excerpt, new.content, new.tags);
+END;
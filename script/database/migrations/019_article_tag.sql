@@ -0,0 +1,28 @@
+-- 标签规范化：标签名大小写不敏感唯一
+CREATE TABLE t_tag (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL COLLATE NOCASE UNIQUE,            -- 标签名（保留首次出现时的写法）
+    created DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE t_article_tag (
+    article_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,                           -- 标签在文章中的顺序
+    PRIMARY KEY (article_id, tag_id)
+);
+
+CREATE INDEX idx_article_tag_tag_id ON t_article_tag(tag_id);
+
+-- 一次性迁移 t_article.tags 中的 JSON 标签（该列此后仅作为全文检索与版本快照的冗余副本）
+INSERT OR IGNORE INTO t_tag (name)
+SELECT trim(j.value)
+FROM t_article a, json_each(a.tags) j
+WHERE json_valid(a.tags) AND json_type(a.tags) = 'array' AND trim(j.value) <> ''
+ORDER BY a.id, j.key;
+
+INSERT OR IGNORE INTO t_article_tag (article_id, tag_id, position)
+SELECT a.id, t.id, j.key
+FROM t_article a, json_each(a.tags) j
+JOIN t_tag t ON t.name = trim(j.value)
+WHERE json_valid(a.tags) AND json_type(a.tags) = 'array' AND trim(j.value) <> '';
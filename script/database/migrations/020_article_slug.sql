@@ -0,0 +1,13 @@
+-- 文章 slug：由标题生成（中文转写为拼音），已有文章在服务启动时补齐
+ALTER TABLE t_article ADD COLUMN slug TEXT;
+
+CREATE UNIQUE INDEX idx_article_slug ON t_article(slug);
+
+-- 改名前的旧 slug，访问时重定向到当前 slug
+CREATE TABLE t_article_slug_alias (
+    slug TEXT PRIMARY KEY,
+    article_id INTEGER NOT NULL,                         -- 文章ID
+    created DATETIME NOT NULL
+);
+
+CREATE INDEX idx_article_slug_alias_article_id ON t_article_slug_alias(article_id);
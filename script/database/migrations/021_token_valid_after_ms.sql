@@ -0,0 +1,2 @@
+-- token_valid_after 改为毫秒时间戳：秒级比较时，与修改密码、禁用等操作同一秒签发的令牌仍然有效
+UPDATE t_user SET token_valid_after = token_valid_after * 1000;
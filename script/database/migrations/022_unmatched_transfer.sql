@@ -0,0 +1,20 @@
+-- 未能匹配订单的链上转账（如订单过期后才到账、金额不符），留待人工对账
+CREATE TABLE t_unmatched_transfer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    network TEXT NOT NULL,
+    tx TEXT NOT NULL,
+    from_address TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    amount TEXT NOT NULL,                                -- 十进制字符串
+    block_time DATETIME NOT NULL,                        -- 出块时间
+    order_id INTEGER,                                    -- 该收款地址最近的订单（对账参考）
+    source TEXT NOT NULL,                                -- watcher | webhook
+    status TEXT NOT NULL DEFAULT 'open',                 -- open | resolved
+    note TEXT,                                           -- 处理说明
+    resolved_by TEXT,                                    -- 处理人：admin:<用户ID>
+    resolved_at DATETIME,
+    created DATETIME NOT NULL
+);
+
+CREATE UNIQUE INDEX idx_unmatched_transfer_tx ON t_unmatched_transfer(network, tx);
+CREATE INDEX idx_unmatched_transfer_status ON t_unmatched_transfer(status, created);
@@ -0,0 +1,2 @@
+-- 说明（无结构变更）：t_payment_webhook.payload 对验签失败的请求仅保存摘要与开头部分，
+-- 格式为 "[sha256=<hex> bytes=<长度>] <开头内容>"；验签通过的请求保存完整请求体
@@ -0,0 +1,2 @@
+-- 说明（无结构变更）：t_user.role 新增 editor 角色，取值为 user | editor | admin；
+-- editor 可管理文章，管理员通过 SQL 指定，如 UPDATE t_user SET role = 'editor' WHERE email = ...